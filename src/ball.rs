@@ -0,0 +1,726 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle, collide_aabb::collide},
+};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    AppState, GameSet, PauseState, Velocity,
+    collide::{self, Contact},
+    brick::{DropTable, Explosions, Explosive, Motion},
+    config::{BALL_COLOR, BallConfig, GameConfig},
+    effect::{ActiveEffects, Effect},
+    grid::{BrickGrid, SpatialHash},
+    level::{Brick, LevelRules, WallBlock},
+    paddle::{Paddle, PaddleMotion},
+    score::Lives,
+};
+
+#[derive(Component)]
+pub struct Ball;
+
+/// The kinds of balls, levels set how much damage each one does to bricks.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BallKind {
+    #[default]
+    Normal,
+    Fireball,
+}
+
+#[derive(Bundle)]
+pub struct BallBundle {
+    mesh: MaterialMesh2dBundle<ColorMaterial>,
+    ball: Ball,
+    kind: BallKind,
+    velocity: Velocity,
+    substep: Substep,
+}
+
+impl BallBundle {
+    pub fn new(mesh: Mesh2dHandle, material: Handle<ColorMaterial>, translation: Vec3, radius: f32, velocity: Vec2) -> Self {
+        Self {
+            mesh: MaterialMesh2dBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(translation).with_scale(Vec2::splat(radius * 2.0).extend(0.0)),
+                ..default()
+            },
+            ball: Ball,
+            kind: BallKind::default(),
+            velocity: Velocity(velocity),
+            substep: Substep::default(),
+        }
+    }
+
+    /// A ball [`Held`] on a random spot of the top of the paddle at `paddle`.
+    pub fn on_paddle(meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>, paddle: &Transform, config: &BallConfig) -> (Self, Held) {
+        let mut rng = rand::thread_rng();
+        let held = Held {
+            offset: rng.gen_range(-0.5..0.5) * paddle.scale.x,
+            timer: Timer::from_seconds(config.auto_launch, TimerMode::Once),
+        };
+        let translation = held.position(paddle, config.radius).extend(10.0);
+        let ball = Self::new(
+            meshes.add(shape::Circle::default().into()).into(),
+            materials.add(ColorMaterial::from(BALL_COLOR)),
+            translation,
+            config.radius,
+            Vec2::ZERO,
+        );
+        (ball, held)
+    }
+}
+
+/// A served ball riding the paddle, `offset` pixels right of its center, until
+/// it is launched or `timer` runs out.
+#[derive(Component)]
+pub struct Held {
+    pub offset: f32,
+    pub timer: Timer,
+}
+
+impl Held {
+    fn position(&self, paddle: &Transform, radius: f32) -> Vec2 {
+        Vec2::new(
+            paddle.translation.x + self.offset,
+            paddle.translation.y + paddle.scale.y / 2.0 + radius,
+        )
+    }
+}
+
+/// Where a ball that already bounced during the current tick was at the
+/// bounce, and how many seconds into the tick. `check_collider_ball` moves it
+/// on from there for the rest of the tick, instead of from where it started.
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq)]
+pub struct Substep(pub Option<(Vec2, f32)>);
+
+/// Number of balls in play.
+#[derive(Resource)]
+pub struct GenBallController {
+    pub ball_count: i32,
+}
+
+impl GenBallController {
+    pub fn new() -> Self {
+        Self {
+            ball_count: 0,
+        }
+    }
+}
+
+impl Default for GenBallController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sent for every brick destroyed, by a ball or otherwise, at the brick's
+/// position, with the points it is worth and the drop table of its type.
+#[derive(Event, Default)]
+pub struct CollisionEvent {
+    pub pos: Vec2,
+    pub score: i32,
+    pub drop_table: Option<String>,
+}
+
+/// Launches every [`Held`] ball, sent on a click, touch or Space.
+#[derive(Event, Default)]
+pub struct LaunchEvent;
+
+/// Serves a ball when a level starts, moves the balls and bounces them off each
+/// other, the bricks and the arena. Losing the last ball costs a life and
+/// serves a new one, or ends the game when no life is left. Balls are removed
+/// when the level ends.
+///
+/// Served balls are [`Held`] on the paddle and launched in the direction the
+/// paddle would bounce them from where they sit, see
+/// [`PaddleReflection`](crate::paddle::PaddleReflection).
+pub struct BallPlugin;
+
+impl Plugin for BallPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GenBallController::new())
+            .init_resource::<ActiveEffects>()
+            .init_resource::<Lives>()
+            .add_event::<CollisionEvent>()
+            .add_event::<LaunchEvent>()
+            .add_systems(OnEnter(AppState::Level), serve_ball)
+            .add_systems(OnExit(AppState::Level), despawn_balls)
+            .add_systems(OnExit(AppState::GameOver), reset_balls)
+            .add_systems(Update, (
+                (read_launch_input, launch_held_balls)
+                    .chain()
+                    .run_if(in_state(AppState::Level).and_then(in_state(PauseState::Running))),
+                check_ball_out_range,
+                apply_ball_config.run_if(resource_changed::<GameConfig>().or_else(resource_changed::<ActiveEffects>())),
+            ))
+            .add_systems(FixedUpdate, carry_held_balls.in_set(GameSet::Movement))
+            .add_systems(FixedUpdate, (
+                check_collider_balls.run_if(ball_collisions_enabled),
+                check_collider_ball,
+            ).chain().in_set(GameSet::BallCollision));
+    }
+}
+
+fn serve_ball(
+    mut commands: Commands,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    mut controller: ResMut<GenBallController>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
+) {
+    if controller.ball_count > 0 {
+        return
+    }
+    commands.spawn(BallBundle::on_paddle(&mut meshes, &mut materials, paddle_query.single(), &config.ball));
+    controller.ball_count = 1;
+}
+
+fn read_launch_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut launch_events: EventWriter<LaunchEvent>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space)
+        || mouse_input.just_pressed(MouseButton::Left)
+        || touches.any_just_pressed()
+    {
+        launch_events.send(LaunchEvent);
+    }
+}
+
+pub(crate) fn launch_held_balls(
+    mut commands: Commands,
+    mut launch_events: EventReader<LaunchEvent>,
+    mut ball_query: Query<(Entity, &mut Held, &mut Velocity), With<Ball>>,
+    paddle_query: Query<(&Transform, &PaddleMotion), With<Paddle>>,
+    effects: Res<ActiveEffects>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+) {
+    let launch = launch_events.read().count() > 0;
+    let Ok((paddle_transform, motion)) = paddle_query.get_single() else {
+        return
+    };
+
+    for (entity, mut held, mut velocity) in &mut ball_query {
+        if !launch && !held.timer.tick(time.delta()).finished() {
+            continue
+        }
+
+        let offset = held.offset / (paddle_transform.scale.x / 2.0);
+        velocity.0 = config.paddle.reflection.reflect(
+            offset,
+            Vec2::new(0.0, -effects.ball_speed(&config)),
+            motion.velocity,
+            config.paddle.min_bounce_angle,
+        );
+        commands.entity(entity).remove::<Held>();
+    }
+}
+
+/// Keeps the held balls on the paddle once it moved.
+fn carry_held_balls(
+    mut ball_query: Query<(&mut Transform, &Held), (With<Ball>, Without<Paddle>)>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+) {
+    let Ok(paddle_transform) = paddle_query.get_single() else {
+        return
+    };
+
+    for (mut transform, held) in &mut ball_query {
+        let position = held.position(paddle_transform, transform.scale.x * 0.5);
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn despawn_balls(
+    mut commands: Commands,
+    ball_query: Query<Entity, With<Ball>>,
+    mut controller: ResMut<GenBallController>,
+) {
+    for entity in &ball_query {
+        commands.entity(entity).despawn();
+    }
+    controller.ball_count = 0;
+}
+
+fn reset_balls(mut commands: Commands) {
+    commands.insert_resource(GenBallController::new());
+}
+
+/// Applies a new ball radius and speed, from the config or the ball effects, to
+/// the balls in play. Velocities are scaled by the change of speed so that they
+/// keep their direction.
+fn apply_ball_config(
+    mut ball_query: Query<(&mut Transform, &mut Velocity), With<Ball>>,
+    effects: Res<ActiveEffects>,
+    config: Res<GameConfig>,
+    mut speed: Local<Option<f32>>,
+) {
+    let target = effects.ball_speed(&config);
+    let ratio = speed.filter(|&speed| speed > 0.0).map_or(1.0, |speed| target / speed);
+    *speed = Some(target);
+
+    for (mut transform, mut velocity) in &mut ball_query {
+        transform.scale = Vec2::splat(config.ball.radius * 2.0).extend(0.0);
+        velocity.0 *= ratio;
+    }
+}
+
+fn check_ball_out_range(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<Ball>>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    mut controller: ResMut<GenBallController>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut lives: ResMut<Lives>,
+    mut state: ResMut<NextState<AppState>>,
+    config: Res<GameConfig>,
+) {
+    let bottom_edge = -config.half_edge_size().y;
+    for (entity, ball_transform) in &query {
+        if ball_transform.translation.y + ball_transform.scale.x * 0.5 <= bottom_edge {
+            commands.entity(entity).despawn();
+            controller.ball_count -= 1;
+
+            if controller.ball_count == 0 {
+                lives.0 = lives.0.saturating_sub(1);
+                if lives.0 == 0 {
+                    state.set(AppState::GameOver);
+                    return
+                }
+
+                let paddle_transform = paddle_query.single();
+                commands.spawn(BallBundle::on_paddle(&mut meshes, &mut materials, paddle_transform, &config.ball));
+                controller.ball_count += 1;
+            }
+        }
+    }
+}
+
+fn check_collider_ball(
+    mut commands: Commands,
+    mut ball_query: Query<(&mut Transform, &mut Velocity, &BallKind, &mut Substep), (With<Ball>, Without<Held>)>,
+    mut brick_query: Query<(&Transform, AnyOf<(&mut Brick, &WallBlock)>, Option<&Explosive>, Option<&Motion>, Option<&DropTable>), Without<Ball>>,
+    grid: Res<BrickGrid>,
+    mut explosions: ResMut<Explosions>,
+    mut candidates: Local<Vec<Entity>>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    rules: Res<LevelRules>,
+    effects: Res<ActiveEffects>,
+    mut collision_events: EventWriter<CollisionEvent>
+) {
+    let brick_collider_size = config.brick.collider_size();
+    let fireball = effects.is_active(Effect::Fireball);
+    let shield = effects.is_active(Effect::Shield);
+    for (mut ball_transform, mut ball_velocity, &kind, mut substep) in &mut ball_query {
+        let kind = if fireball { BallKind::Fireball } else { kind };
+        let mut remaining = time.delta_seconds();
+        let radius = ball_transform.scale.x * 0.5;
+        if let Some((pos, elapsed)) = substep.0.take() {
+            ball_transform.translation = pos.extend(ball_transform.translation.z);
+            remaining = (remaining - elapsed).max(0.0);
+        }
+
+        //一帧内可能连续碰撞多次, 依次消耗剩余时间
+        for _ in 0..config.ball.max_bounces_per_tick {
+            let ball_translation = ball_transform.translation.truncate();
+            let future_ball_translation = ball_translation + ball_velocity.0 * remaining;
+            let check_box_translation = ((future_ball_translation + ball_translation) / 2.0).extend(0.0);
+
+            let check_box_size = Vec2::new(
+                (future_ball_translation.x - ball_translation.x).abs() + radius * 2.0,
+                (future_ball_translation.y - ball_translation.y).abs() + radius * 2.0,
+            );
+
+            let mut collision: Option<(Contact, Entity, Vec2)> = None;
+
+            //从网格中取出扫掠范围内的砖块
+            grid.query_aabb(
+                check_box_translation.truncate() - check_box_size / 2.0,
+                check_box_translation.truncate() + check_box_size / 2.0,
+                &mut candidates,
+            );
+
+            for &child in candidates.iter() {
+                let Ok((brick_transform, (brick_option, _), _, motion, _)) = brick_query.get(child) else {
+                    continue
+                };
+
+                if let Some(brick) = brick_option {
+                    if brick.destroy {
+                        continue
+                    }
+                }
+
+                let brick_velocity = motion.map_or(Vec2::ZERO, |motion| motion.velocity);
+                // the swept box only rules out bricks standing still
+                if brick_velocity == Vec2::ZERO && collide(
+                    check_box_translation,
+                    check_box_size,
+                    brick_transform.translation,
+                    brick_collider_size,
+                ).is_none() {
+                    continue
+                }
+                // moving bricks already are where they end the tick, sweep the ball
+                // relative to the brick from where the brick is now
+                let contact = collide::time_of_collide_circle_rect(
+                    ball_translation,
+                    radius,
+                    ball_velocity.0 - brick_velocity,
+                    brick_transform.translation.truncate() - brick_velocity * remaining,
+                    brick_collider_size,
+                );
+
+                if let Some(contact) = contact {
+                    if contact.toi <= remaining {
+                        match collision {
+                            Some((c, _, _)) => {
+                                if contact.toi < c.toi {
+                                    collision = Some((contact, child, brick_velocity))
+                                }
+                            }
+                            None => {
+                                collision = Some((contact, child, brick_velocity))
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut edge_collision = collide::time_of_collide_circle_inside_rect(
+                ball_translation,
+                radius,
+                ball_velocity.0,
+                Vec2::ZERO,
+                config.edge_size,
+            );
+
+            if let Some(contact) = edge_collision {
+                // the bottom edge is open unless shielded, balls leaving through it are handled by check_ball_out_range
+                if contact.toi > remaining || (contact.normal.y > 0.0 && !shield) {
+                    edge_collision = None;
+                } else if let Some((c, _, _)) = collision {
+                    if contact.toi < c.toi {
+                        collision = None;
+                    } else {
+                        edge_collision = None;
+                    }
+                }
+            }
+
+            let (contact, surface_velocity, pierce) = match (collision, edge_collision) {
+                (Some((contact, child, brick_velocity)), _) => {
+                    let (transform, (brick_option, _), explosive, _, drop_table) = brick_query.get_mut(child).unwrap();
+
+                    let mut pierce = false;
+                    if let Some(mut brick) = brick_option {
+                        if brick.damage(rules.damage(kind)) {
+                            pierce = fireball;
+                            commands.entity(child).despawn();
+
+                            collision_events.send(CollisionEvent {
+                                pos: transform.translation.truncate(),
+                                score: brick.score,
+                                drop_table: drop_table.map(|table| table.0.clone()),
+                            });
+                            if let Some(explosive) = explosive {
+                                explosions.push(transform.translation.truncate(), explosive.0);
+                            }
+                        }
+                    }
+                    (contact, brick_velocity, pierce)
+                }
+                (None, Some(edge_contact)) => (edge_contact, Vec2::ZERO, false),
+                (None, None) => {
+                    ball_transform.translation.x += ball_velocity.x * remaining;
+                    ball_transform.translation.y += ball_velocity.y * remaining;
+                    remaining = 0.0;
+                    break
+                }
+            };
+
+            ball_transform.translation.x += ball_velocity.x * contact.toi;
+            ball_transform.translation.y += ball_velocity.y * contact.toi;
+            remaining -= contact.toi;
+
+            // fireballs go on through the bricks they break
+            if pierce {
+                continue
+            }
+            // bounce in the frame of the surface, a moving brick carries the ball along
+            ball_velocity.0 = surface_velocity
+                + collide::reflect(ball_velocity.0 - surface_velocity, contact.normal, config.ball.restitution, config.ball.friction);
+        }
+
+        // 达到迭代上限时丢弃剩余时间, 下一帧再继续, 避免穿透
+        if remaining > 0.0 {
+            debug!("ball bounce limit reached, {}s of movement dropped", remaining);
+        }
+    }
+}
+
+fn ball_collisions_enabled(config: Res<GameConfig>) -> bool {
+    config.ball.ball_collisions
+}
+
+/// Elastic collisions between balls, only run when [`BallConfig::ball_collisions`] is on.
+///
+/// Each ball takes part in at most its earliest ball contact of the tick, and
+/// balls that already bounced off the paddle this tick are left out. The
+/// colliding balls get their new velocities and a [`Substep`] at the impact,
+/// from where `check_collider_ball` moves them on.
+fn check_collider_balls(
+    mut ball_query: Query<(Entity, &Transform, &mut Velocity, &mut Substep), (With<Ball>, Without<Held>)>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut hash: Local<SpatialHash>,
+    mut balls: Local<Vec<(Entity, Vec2, f32, Vec2)>>,
+    mut candidates: Local<Vec<usize>>,
+    mut contacts: Local<Vec<(Contact, usize, usize)>>,
+    mut resolved: Local<Vec<bool>>,
+) {
+    let delta = time.delta_seconds();
+
+    balls.clear();
+    hash.reset(config.ball.hash_cell_size());
+    for (entity, transform, velocity, substep) in &ball_query {
+        if substep.0.is_some() {
+            continue
+        }
+        let pos = transform.translation.truncate();
+        let radius = transform.scale.x * 0.5;
+        let future = pos + velocity.0 * delta;
+        hash.insert(balls.len(), pos.min(future) - Vec2::splat(radius), pos.max(future) + Vec2::splat(radius));
+        balls.push((entity, pos, radius, velocity.0));
+    }
+
+    contacts.clear();
+    for (i, &(_, pos, radius, velocity)) in balls.iter().enumerate() {
+        let future = pos + velocity * delta;
+        hash.query(pos.min(future) - Vec2::splat(radius), pos.max(future) + Vec2::splat(radius), &mut candidates);
+
+        for &j in candidates.iter().filter(|&&j| j > i) {
+            let (_, other_pos, other_radius, other_velocity) = balls[j];
+            let Some(contact) = collide::time_of_collide_circle_circle(pos, radius, velocity, other_pos, other_radius, other_velocity) else {
+                continue
+            };
+            if contact.toi <= delta {
+                contacts.push((contact, i, j));
+            }
+        }
+    }
+
+    contacts.sort_unstable_by(|a, b| a.0.toi.total_cmp(&b.0.toi));
+
+    resolved.clear();
+    resolved.resize(balls.len(), false);
+    for &(contact, i, j) in contacts.iter() {
+        if resolved[i] || resolved[j] {
+            continue
+        }
+        resolved[i] = true;
+        resolved[j] = true;
+
+        let (v1, v2) = collide::resolve_circle_circle(balls[i].3, balls[j].3, contact.normal, config.ball.restitution);
+        for (index, velocity) in [(i, v1), (j, v2)] {
+            let (entity, pos, _, old_velocity) = balls[index];
+            if let Ok((_, _, mut ball_velocity, mut substep)) = ball_query.get_mut(entity) {
+                substep.0 = Some((pos + old_velocity * contact.toi, contact.toi));
+                ball_velocity.0 = velocity;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{
+        Ball, BallBundle, BallKind, CollisionEvent, GenBallController, Held, LaunchEvent, Substep,
+        ball_collisions_enabled, check_ball_out_range, check_collider_ball, check_collider_balls,
+        carry_held_balls, launch_held_balls,
+    };
+    use crate::{
+        AppState, Velocity,
+        brick::{BrickMotion, Explosions, Motion},
+        config::GameConfig,
+        effect::ActiveEffects,
+        grid::BrickGrid,
+        level::{LevelRules, WallBlock},
+        paddle::{Paddle, PaddleMotion},
+        score::Lives,
+    };
+
+    fn lose_ball(app: &mut App) {
+        let bottom = -app.world.resource::<GameConfig>().half_edge_size().y;
+        app.world.spawn((
+            Transform::from_xyz(0.0, bottom - 10.0, 0.0).with_scale(Vec3::new(8.0, 8.0, 0.0)),
+            Ball,
+            Velocity(Vec2::new(0.0, -200.0)),
+        ));
+        app.update();
+    }
+
+    #[test]
+    fn test_losing_the_last_life_ends_the_game() {
+        let mut app = App::new();
+        app.add_state::<AppState>()
+            .init_resource::<GameConfig>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .insert_resource(Lives(2))
+            .insert_resource(GenBallController { ball_count: 1 })
+            .add_systems(Update, check_ball_out_range);
+        app.world.spawn((Transform::default(), Paddle));
+
+        lose_ball(&mut app);
+        assert_eq!(app.world.resource::<Lives>().0, 1);
+        assert_eq!(app.world.resource::<GenBallController>().ball_count, 1);
+        let balls: Vec<Entity> = app.world.query_filtered::<Entity, With<Ball>>().iter(&app.world).collect();
+        assert_eq!(balls.len(), 1, "a new ball is served");
+
+        app.world.despawn(balls[0]);
+        lose_ball(&mut app);
+        app.update();
+        assert_eq!(app.world.resource::<Lives>().0, 0);
+        assert_eq!(*app.world.resource::<State<AppState>>().get(), AppState::GameOver);
+        assert_eq!(app.world.query::<&Ball>().iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn test_held_ball_rides_the_paddle_until_launched() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameConfig>()
+            .init_resource::<ActiveEffects>()
+            .add_event::<LaunchEvent>()
+            .add_systems(Update, (launch_held_balls, carry_held_balls).chain());
+
+        let paddle = app.world.spawn((
+            Transform::from_xyz(0.0, -200.0, 0.0).with_scale(Vec3::new(80.0, 10.0, 0.0)),
+            PaddleMotion::default(),
+            Paddle,
+        )).id();
+        let config = app.world.resource::<GameConfig>().ball.clone();
+        let bundle = {
+            let paddle_transform = *app.world.get::<Transform>(paddle).unwrap();
+            let mut meshes = Assets::<Mesh>::default();
+            let mut materials = Assets::<ColorMaterial>::default();
+            BallBundle::on_paddle(&mut meshes, &mut materials, &paddle_transform, &config)
+        };
+        let ball = app.world.spawn(bundle).id();
+
+        app.world.get_mut::<Transform>(paddle).unwrap().translation.x = 100.0;
+        app.update();
+        let held = app.world.get::<Held>(ball).unwrap();
+        let translation = app.world.get::<Transform>(ball).unwrap().translation;
+        assert_eq!(translation.x, 100.0 + held.offset);
+        assert_eq!(translation.y, -200.0 + 5.0 + config.radius);
+        assert_eq!(app.world.get::<Velocity>(ball).unwrap().0, Vec2::ZERO);
+
+        app.world.send_event(LaunchEvent);
+        app.update();
+        assert!(app.world.get::<Held>(ball).is_none());
+        let velocity = app.world.get::<Velocity>(ball).unwrap().0;
+        assert!(velocity.y > 0.0);
+        assert!((velocity.length() - config.speed).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_moving_brick_carries_the_ball() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<GameConfig>()
+            .init_resource::<LevelRules>()
+            .init_resource::<Explosions>()
+            .init_resource::<ActiveEffects>()
+            .insert_resource(BrickGrid::new(Vec2::splat(48.0)))
+            .add_event::<CollisionEvent>()
+            .add_systems(Update, check_collider_ball);
+        app.world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.1));
+
+        // ends the tick at y = 5 after moving down 10, 5 above the still ball
+        let pos = Vec2::new(0.0, 5.0);
+        let mut motion = Motion::new(BrickMotion::PingPong { offset: Vec2::ZERO, period: 1.0 }, pos);
+        motion.velocity = Vec2::new(0.0, -100.0);
+        let wall = app.world.spawn((Transform::from_translation(pos.extend(0.0)), WallBlock, motion)).id();
+        let size = app.world.resource::<GameConfig>().brick.collider_size();
+        app.world.resource_mut::<BrickGrid>().insert(wall, pos + Vec2::new(0.0, 5.0), size + Vec2::new(0.0, 10.0));
+
+        let ball = app.world.spawn((
+            Transform::from_scale(Vec3::new(8.0, 8.0, 0.0)),
+            Ball,
+            BallKind::Normal,
+            Velocity(Vec2::ZERO),
+            Substep::default(),
+        )).id();
+        app.update();
+
+        let velocity = app.world.get::<Velocity>(ball).unwrap().0;
+        assert!(velocity.abs_diff_eq(Vec2::new(0.0, -200.0), 1e-3), "{:?}", velocity);
+        assert!(app.world.get::<Transform>(ball).unwrap().translation.y < 0.0);
+    }
+
+    /// Runs one 0.3s tick of two balls of radius 4 heading at each other at
+    /// 100 units per second from 40 apart, and returns where they end up.
+    fn head_on_balls(ball_collisions: bool) -> [(Vec2, Vec2); 2] {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<GameConfig>()
+            .init_resource::<LevelRules>()
+            .init_resource::<Explosions>()
+            .init_resource::<ActiveEffects>()
+            .insert_resource(BrickGrid::new(Vec2::splat(48.0)))
+            .add_event::<CollisionEvent>()
+            .add_systems(Update, (check_collider_balls.run_if(ball_collisions_enabled), check_collider_ball).chain());
+        app.world.resource_mut::<GameConfig>().ball.ball_collisions = ball_collisions;
+        app.world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.3));
+
+        let balls = [(-20.0, 100.0), (20.0, -100.0)].map(|(x, speed)| {
+            app.world.spawn((
+                Transform::from_xyz(x, 0.0, 0.0).with_scale(Vec3::new(8.0, 8.0, 0.0)),
+                Ball,
+                BallKind::Normal,
+                Velocity(Vec2::new(speed, 0.0)),
+                Substep::default(),
+            )).id()
+        });
+        app.update();
+
+        balls.map(|ball| (
+            app.world.get::<Transform>(ball).unwrap().translation.truncate(),
+            app.world.get::<Velocity>(ball).unwrap().0,
+        ))
+    }
+
+    #[test]
+    fn test_head_on_balls_bounce_off_each_other() {
+        // they touch 0.16s in and spend the other 0.14s moving apart
+        let [(pos_a, velocity_a), (pos_b, velocity_b)] = head_on_balls(true);
+        assert!(velocity_a.abs_diff_eq(Vec2::new(-100.0, 0.0), 1e-3), "{:?}", velocity_a);
+        assert!(velocity_b.abs_diff_eq(Vec2::new(100.0, 0.0), 1e-3), "{:?}", velocity_b);
+        assert!(pos_a.abs_diff_eq(Vec2::new(-18.0, 0.0), 1e-3), "{:?}", pos_a);
+        assert!(pos_b.abs_diff_eq(Vec2::new(18.0, 0.0), 1e-3), "{:?}", pos_b);
+        assert!(pos_a.distance(pos_b) >= 8.0);
+    }
+
+    #[test]
+    fn test_balls_pass_through_each_other_without_ball_collisions() {
+        let [(pos_a, velocity_a), (pos_b, velocity_b)] = head_on_balls(false);
+        assert_eq!(velocity_a, Vec2::new(100.0, 0.0));
+        assert_eq!(velocity_b, Vec2::new(-100.0, 0.0));
+        assert!(pos_a.abs_diff_eq(Vec2::new(10.0, 0.0), 1e-3), "{:?}", pos_a);
+        assert!(pos_b.abs_diff_eq(Vec2::new(-10.0, 0.0), 1e-3), "{:?}", pos_b);
+    }
+}
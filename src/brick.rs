@@ -0,0 +1,354 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Serialize, Deserialize};
+
+use crate::{ball::CollisionEvent, config::GameConfig, grid::BrickGrid, level::Brick};
+
+/// The brick types levels can use, by name.
+///
+/// Read from [`BRICK_TYPES_PATH`](crate::config::BRICK_TYPES_PATH) when a
+/// level loads, the level fails to load if one of its bricks names a type
+/// missing from here.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct BrickTypeRegistry {
+    pub types: HashMap<String, BrickType>,
+}
+
+impl BrickTypeRegistry {
+    pub fn get(&self, name: &str) -> Option<&BrickType> {
+        self.types.get(name)
+    }
+}
+
+/// What a brick is made of, bricks of a level can override `color`, `hp`
+/// and `score`.
+///
+/// `sprite` is a texture path drawn over the brick instead of a plain color,
+/// which then tints it. `drop_table` names the reward drop table used when the
+/// brick breaks, `explosion` makes it blow up when it does.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BrickType {
+    pub collider: BrickCollider,
+    pub hp: u32,
+    pub score: i32,
+    pub color: Color,
+    pub sprite: Option<String>,
+    pub drop_table: Option<String>,
+    pub explosion: Option<Explosion>,
+}
+
+impl Default for BrickType {
+    fn default() -> Self {
+        Self {
+            collider: BrickCollider::default(),
+            hp: 1,
+            score: 1,
+            color: Color::WHITE,
+            sprite: None,
+            drop_table: None,
+            explosion: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrickCollider {
+    /// Broken by the balls, the level is cleared once all of them are.
+    #[default]
+    Breakable,
+    /// Never breaks.
+    Wall,
+}
+
+/// Name of the [`BrickType`] a brick was spawned from.
+#[derive(Component, Clone, Debug, Deref)]
+pub struct BrickTypeName(pub String);
+
+/// The reward drop table of a brick, from its [`BrickType`].
+#[derive(Component, Clone, Debug, Deref)]
+pub struct DropTable(pub String);
+
+/// Blast of an explosive brick, going off `delay` seconds after the brick
+/// broke and taking `damage` hit points off every brick whose center is
+/// within `radius`. Explosive bricks broken by it explode in turn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Explosion {
+    pub radius: f32,
+    pub damage: u32,
+    pub delay: f32,
+}
+
+impl Default for Explosion {
+    fn default() -> Self {
+        Self {
+            radius: 24.0,
+            damage: 1,
+            delay: 0.1,
+        }
+    }
+}
+
+/// The explosion of a brick, from its [`BrickType`].
+#[derive(Component, Clone, Copy, Debug, Deref)]
+pub struct Explosive(pub Explosion);
+
+/// Sent for every blast that broke bricks, with its position and the number
+/// of bricks it broke.
+#[derive(Event, Debug)]
+pub struct ExplosionEvent {
+    pub pos: Vec2,
+    pub bricks: u32,
+}
+
+/// Explosions of broken bricks waiting to go off.
+#[derive(Resource, Default)]
+pub struct Explosions(Vec<(Vec2, Explosion, Timer)>);
+
+impl Explosions {
+    pub fn push(&mut self, pos: Vec2, explosion: Explosion) {
+        self.0.push((pos, explosion, Timer::from_seconds(explosion.delay, TimerMode::Once)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Sets off the pending explosions whose delay is over. Bricks broken by them
+/// are despawned and scored like bricks hit by a ball, explosive ones queue
+/// their own explosion, which goes off on a later tick.
+pub(crate) fn detonate_explosions(
+    mut commands: Commands,
+    mut explosions: ResMut<Explosions>,
+    mut brick_query: Query<(&Transform, &mut Brick, Option<&Explosive>, Option<&DropTable>)>,
+    grid: Res<BrickGrid>,
+    time: Res<Time>,
+    mut ready: Local<Vec<(Vec2, Explosion)>>,
+    mut candidates: Local<Vec<Entity>>,
+    mut collision_events: EventWriter<CollisionEvent>,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    ready.clear();
+    explosions.0.retain_mut(|(pos, explosion, timer)| {
+        if timer.tick(time.delta()).finished() {
+            ready.push((*pos, *explosion));
+            false
+        } else {
+            true
+        }
+    });
+
+    for &(pos, explosion) in ready.iter() {
+        let reach = Vec2::splat(explosion.radius);
+        grid.query_aabb(pos - reach, pos + reach, &mut candidates);
+
+        let mut bricks = 0;
+        for &entity in candidates.iter() {
+            // walls have no Brick and are left standing
+            let Ok((transform, mut brick, explosive, drop_table)) = brick_query.get_mut(entity) else {
+                continue
+            };
+            let brick_pos = transform.translation.truncate();
+            if brick.destroy || brick_pos.distance(pos) > explosion.radius {
+                continue
+            }
+
+            if brick.damage(explosion.damage) {
+                bricks += 1;
+                commands.entity(entity).despawn();
+                collision_events.send(CollisionEvent {
+                    pos: brick_pos,
+                    score: brick.score,
+                    drop_table: drop_table.map(|table| table.0.clone()),
+                });
+                if let Some(explosive) = explosive {
+                    explosions.push(brick_pos, explosive.0);
+                }
+            }
+        }
+
+        if bricks > 0 {
+            explosion_events.send(ExplosionEvent { pos, bricks });
+        }
+    }
+}
+
+pub(crate) fn clear_explosions(mut explosions: ResMut<Explosions>) {
+    explosions.0.clear();
+}
+
+/// Path a brick of a level moves along, relative to its position in the level.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BrickMotion {
+    /// Back and forth between its position and `offset` away from it, a round
+    /// trip taking `period` seconds.
+    PingPong { offset: Vec2, period: f32 },
+    /// Counterclockwise around the point `radius` left of its position,
+    /// starting from it, a lap taking `period` seconds.
+    Circle { radius: f32, period: f32 },
+    /// From its position through each of `points` and back, at `speed`.
+    Waypoints { points: Vec<Vec2>, speed: f32 },
+}
+
+impl BrickMotion {
+    /// Offset from the level position `time` seconds into the level.
+    pub fn offset(&self, time: f32) -> Vec2 {
+        match self {
+            BrickMotion::PingPong { offset, period } if *period > 0.0 => {
+                let phase = (time / period).fract();
+                *offset * (1.0 - (1.0 - phase * 2.0).abs())
+            }
+            BrickMotion::Circle { radius, period } if *period > 0.0 => {
+                (Vec2::from_angle(time / period * TAU) - Vec2::X) * *radius
+            }
+            BrickMotion::Waypoints { points, speed } if !points.is_empty() && *speed > 0.0 => {
+                let segments = || {
+                    std::iter::once(Vec2::ZERO).chain(points.iter().copied())
+                        .zip(points.iter().copied().chain(std::iter::once(Vec2::ZERO)))
+                };
+                let length: f32 = segments().map(|(from, to)| from.distance(to)).sum();
+                if length <= 0.0 {
+                    return Vec2::ZERO
+                }
+
+                let mut distance = (time * speed) % length;
+                for (from, to) in segments() {
+                    let segment = from.distance(to);
+                    if distance < segment {
+                        return from.lerp(to, distance / segment)
+                    }
+                    distance -= segment;
+                }
+                Vec2::ZERO
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+/// A brick moving along `path` from `origin`, its position in the level.
+///
+/// `velocity` is its average velocity over the last tick, in units per second,
+/// which the balls take into account when bouncing off it.
+#[derive(Component, Clone, Debug)]
+pub struct Motion {
+    pub path: BrickMotion,
+    pub origin: Vec2,
+    pub time: f32,
+    pub velocity: Vec2,
+}
+
+impl Motion {
+    pub fn new(path: BrickMotion, origin: Vec2) -> Self {
+        Self {
+            path,
+            origin,
+            time: 0.0,
+            velocity: Vec2::ZERO,
+        }
+    }
+}
+
+/// Moves the bricks along their path to where they are at the end of the tick.
+///
+/// A moving brick is registered in the [`BrickGrid`] over the whole area it
+/// swept during the tick, so balls meeting it anywhere along the way find it.
+pub(crate) fn move_bricks(
+    mut brick_query: Query<(Entity, &mut Transform, &mut Motion)>,
+    mut grid: ResMut<BrickGrid>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+) {
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return
+    }
+
+    for (entity, mut transform, mut motion) in &mut brick_query {
+        let previous = transform.translation.truncate();
+        motion.time += delta;
+        let pos = motion.origin + motion.path.offset(motion.time);
+
+        motion.velocity = (pos - previous) / delta;
+        transform.translation = pos.extend(transform.translation.z);
+        grid.insert(entity, (previous + pos) / 2.0, config.brick.collider_size() + (pos - previous).abs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{detonate_explosions, BrickMotion, Explosion, ExplosionEvent, Explosions, Explosive};
+    use crate::{ball::CollisionEvent, grid::BrickGrid, level::Brick};
+
+    #[test]
+    fn test_explosions_chain_over_ticks() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Explosions>()
+            .insert_resource(BrickGrid::new(Vec2::splat(24.0)))
+            .add_event::<CollisionEvent>()
+            .add_event::<ExplosionEvent>()
+            .add_systems(Update, detonate_explosions);
+
+        let explosion = Explosion { radius: 13.0, damage: 1, delay: 0.0 };
+        // three explosive bricks in a row, then a plain one and one out of reach
+        for x in [0.0, 12.0, 24.0, 36.0, 100.0] {
+            let pos = Vec2::new(x, 0.0);
+            let mut brick = app.world.spawn((Transform::from_translation(pos.extend(0.0)), Brick::new(1, 1, Color::WHITE)));
+            if x < 36.0 {
+                brick.insert(Explosive(explosion));
+            }
+            let entity = brick.id();
+            app.world.resource_mut::<BrickGrid>().insert(entity, pos, Vec2::splat(10.0));
+        }
+
+        let mut reader = app.world.resource::<Events<CollisionEvent>>().get_reader();
+        let mut tick = |app: &mut App| {
+            app.update();
+            let broken = reader.read(app.world.resource::<Events<CollisionEvent>>()).count();
+            let left = app.world.query::<&Brick>().iter(&app.world).count();
+            (broken, left)
+        };
+
+        app.world.resource_mut::<Explosions>().push(Vec2::ZERO, explosion);
+        assert_eq!(tick(&mut app), (2, 3));
+        assert_eq!(tick(&mut app), (1, 2));
+        assert_eq!(tick(&mut app), (1, 1));
+        assert_eq!(tick(&mut app), (0, 1));
+        assert!(app.world.resource::<Explosions>().is_empty());
+    }
+
+    #[test]
+    fn test_motion_paths() {
+        let ping_pong = BrickMotion::PingPong { offset: Vec2::new(40.0, 0.0), period: 2.0 };
+        assert_eq!(ping_pong.offset(0.0), Vec2::ZERO);
+        assert_eq!(ping_pong.offset(0.5), Vec2::new(20.0, 0.0));
+        assert_eq!(ping_pong.offset(1.0), Vec2::new(40.0, 0.0));
+        assert_eq!(ping_pong.offset(1.5), Vec2::new(20.0, 0.0));
+
+        let circle = BrickMotion::Circle { radius: 10.0, period: 4.0 };
+        assert_eq!(circle.offset(0.0), Vec2::ZERO);
+        assert!(circle.offset(1.0).abs_diff_eq(Vec2::new(-10.0, 10.0), 1e-4));
+        assert!(circle.offset(2.0).abs_diff_eq(Vec2::new(-20.0, 0.0), 1e-4));
+
+        // a 10 x 10 square walked at 10 per second
+        let waypoints = BrickMotion::Waypoints {
+            points: vec![Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0)],
+            speed: 10.0,
+        };
+        assert_eq!(waypoints.offset(0.0), Vec2::ZERO);
+        assert!(waypoints.offset(0.5).abs_diff_eq(Vec2::new(5.0, 0.0), 1e-4));
+        assert!(waypoints.offset(1.5).abs_diff_eq(Vec2::new(10.0, 5.0), 1e-4));
+        assert!(waypoints.offset(3.5).abs_diff_eq(Vec2::new(0.0, 5.0), 1e-4));
+        assert!(waypoints.offset(4.5).abs_diff_eq(Vec2::new(5.0, 0.0), 1e-4));
+
+        let stuck = BrickMotion::PingPong { offset: Vec2::X, period: 0.0 };
+        assert_eq!(stuck.offset(1.0), Vec2::ZERO);
+    }
+}
@@ -1,219 +1,509 @@
-use bevy::{math::Vec2, utils::petgraph::matrix_graph::Zero, sprite::collide_aabb::Collision};
+use bevy::math::Vec2;
+
+/// Result of a swept collision test.
+///
+/// `toi` is expressed in the same time unit as the velocity passed in,
+/// `point` is the contact point on the surface of the obstacle and
+/// `normal` is the unit surface normal pointing towards the moving circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub toi: f32,
+    pub point: Vec2,
+    pub normal: Vec2,
+}
+
+const EPSILON: f32 = 1e-6;
 
-fn cross2d(v1:Vec2, v2: Vec2) -> f32 {
-   v1.x*v2.y - v1.y*v2.x
+fn nearest(current: Option<Contact>, candidate: Contact) -> Option<Contact> {
+    match current {
+        Some(c) if c.toi <= candidate.toi => Some(c),
+        _ => Some(candidate),
+    }
 }
 
+/// Smallest non negative root of `|p + v*t| = radius`, only when the point is
+/// moving towards the circle center.
+fn time_of_point_circle(p: Vec2, v: Vec2, radius: f32) -> Option<f32> {
+    let a = v.dot(v);
+    let b = p.dot(v);
+    let c = p.dot(p) - radius * radius;
 
-fn time_of_vertex_edge_parallel(x0: Vec2, v0: Vec2, x1: Vec2, x2: Vec2) -> Vec<Option<f32>> {
-    let a = cross2d(v0, Vec2::ZERO);
-    let b = cross2d(x0 - x1, Vec2::ZERO) + cross2d(v0, x2-x1);
-    let c = cross2d(x0 - x1, x2 - x1);
+    if a < EPSILON || b >= 0.0 {
+        return None;
+    }
+
+    let d = b * b - a * c;
+    if d < 0.0 {
+        return None;
+    }
 
-    let d = b*b - 4.0*a*c;
+    let t = (-b - d.sqrt()) / a;
+    if t < -EPSILON {
+        return None;
+    }
 
-    let mut result = vec![None; 2];
-    let mut result_num = 0;
+    Some(t.max(0.0))
+}
 
-    if a.is_zero() {
-        result[1] = Some(-c / b);
-        return result;
+/// Sweeps a circle moving with velocity `v` against an axis aligned rectangle.
+///
+/// The test is done against the Minkowski sum of the rectangle and the circle,
+/// i.e. a rectangle grown by `radius` on every side with rounded corners, so
+/// hits on the four edges and on the four corner arcs are both reported with
+/// their exact time of impact and normal. A circle already overlapping the
+/// rectangle and moving further into it is reported at `toi` 0 with the normal
+/// of the shortest way out.
+pub(crate) fn time_of_collide_circle_rect(circle: Vec2, radius: f32, v: Vec2, rect: Vec2, rect_size: Vec2) -> Option<Contact> {
+    if v == Vec2::ZERO {
+        return None
     }
 
-    if d < 0.0 {
-        result[0] = Some(-b / (2.0 * a));
-    } else{
-        let q = -(b + b.sin() * d.sqrt()) / 2.0;
-        if a.abs() >  1e-12 * q.abs() {
-            result[result_num] = Some(q / a);
-            result_num += 1;
+    let half = rect_size / 2.0;
+    let p = circle - rect;
+
+    if let Some(contact) = penetration_circle_rect(circle, radius, rect, rect_size) {
+        if v.dot(contact.normal) < 0.0 {
+            return Some(contact);
+        }
+        return None;
+    }
+
+    let mut result = None;
+
+    //边
+    if v.x != 0.0 {
+        let side = -v.x.signum();
+        let face = side * (half.x + radius);
+        if p.x * side >= face * side - EPSILON {
+            let t = ((face - p.x) / v.x).max(0.0);
+            let y = p.y + v.y * t;
+            if y.abs() <= half.y {
+                let normal = Vec2::new(side, 0.0);
+                result = nearest(result, Contact {
+                    toi: t,
+                    point: rect + Vec2::new(side * half.x, y),
+                    normal,
+                });
+            }
+        }
+    }
+
+    if v.y != 0.0 {
+        let side = -v.y.signum();
+        let face = side * (half.y + radius);
+        if p.y * side >= face * side - EPSILON {
+            let t = ((face - p.y) / v.y).max(0.0);
+            let x = p.x + v.x * t;
+            if x.abs() <= half.x {
+                let normal = Vec2::new(0.0, side);
+                result = nearest(result, Contact {
+                    toi: t,
+                    point: rect + Vec2::new(x, side * half.y),
+                    normal,
+                });
+            }
         }
+    }
 
-        if q.abs() > 1e-12*c.abs() {
-            result[result_num] = Some(c / q);
-            result_num += 1;
+    //角
+    for corner in [
+        Vec2::new(-half.x, -half.y),
+        Vec2::new(-half.x, half.y),
+        Vec2::new(half.x, half.y),
+        Vec2::new(half.x, -half.y),
+    ] {
+        let Some(t) = time_of_point_circle(p - corner, v, radius) else {
+            continue
+        };
+
+        let hit = p + v * t;
+        // only the quarter of the arc lying outside the rectangle's edges is part of the rounded shape
+        if hit.x.abs() < half.x || hit.y.abs() < half.y {
+            continue
+        }
+        if hit.x.signum() != corner.x.signum() || hit.y.signum() != corner.y.signum() {
+            continue
         }
 
-        if result_num == 2 && result[0].unwrap() > result[1].unwrap() {
-            let temp = result[0];
-            result[0] = result[1];
-            result[1] = temp;
+        let normal = (hit - corner).normalize_or_zero();
+        if normal == Vec2::ZERO {
+            continue
         }
+        result = nearest(result, Contact {
+            toi: t,
+            point: rect + corner,
+            normal,
+        });
     }
 
     result
 }
 
-pub fn check_collide_point_nearest_edge(point:Vec2, v: Vec2, edges: &Vec<(Vec2,Vec2, Collision)> ) -> Option<(f32, Collision)> {
-    let mut nearest = None;
-
-    for &(x1, x2, collision) in edges.iter() {
-        let toi_result = time_of_vertex_edge_parallel(point, v, x1, x2);
-        for toi in toi_result {
-            if let Some(toi) = toi {
-                if toi > 0.0 &&  !toi.is_infinite() {
-                    let parallel_point = Vec2::new(point.x + v.x * toi, point.y + v.y *toi);
-                    // println!("point: {} parallel_point:{} {} {:?} {}", point, parallel_point, toi, collision, 1e-6);
-                    //     cross2d(parallel_point - x1, x2 - x1).abs());
-                    if parallel_point.x >= x1.x.min(x2.x) - 1e-6 && parallel_point.x <= x1.x.max(x2.x) + 1e-6
-                        && parallel_point.y >= x1.y.min(x2.y) - 1e-6 && parallel_point.y <= x1.y.max(x2.y) + 1e-6 {
-                    // if cross2d(parallel_point - x1, x2-x1).abs() > 1e-10{
-                        match nearest {
-                            Some((t, _)) => {
-                                if toi < t {
-                                    nearest = Some((toi, collision));
-                                }
-                            }
-                            None => {
-                                nearest = Some((toi, collision));
-                            }
-                        }
-                    }
-                }
-            }
+/// Returns the contact of a circle overlapping an axis aligned rectangle, with
+/// `toi` 0 and the normal along which the circle leaves the rectangle fastest.
+pub(crate) fn penetration_circle_rect(circle: Vec2, radius: f32, rect: Vec2, rect_size: Vec2) -> Option<Contact> {
+    let half = rect_size / 2.0;
+    let p = circle - rect;
+    let closest = p.clamp(-half, half);
+    let d = p - closest;
+
+    if d != Vec2::ZERO {
+        if d.length_squared() >= radius * radius {
+            return None;
         }
+        return Some(Contact {
+            toi: 0.0,
+            point: rect + closest,
+            normal: d.normalize(),
+        });
     }
 
-    nearest
+    //圆心在矩形内部
+    let depth = half - p.abs();
+    let normal = if depth.x < depth.y {
+        Vec2::new(if p.x < 0.0 { -1.0 } else { 1.0 }, 0.0)
+    } else {
+        Vec2::new(0.0, if p.y < 0.0 { -1.0 } else { 1.0 })
+    };
+
+    Some(Contact {
+        toi: 0.0,
+        point: rect + Vec2::new(
+            if normal.x == 0.0 { p.x } else { normal.x * half.x },
+            if normal.y == 0.0 { p.y } else { normal.y * half.y },
+        ),
+        normal,
+    })
 }
 
-pub(crate) fn time_of_collide_circle_rect(circle: Vec2, radius: f32, v: Vec2, rect: Vec2, rect_size: Vec2) -> Option<(f32, Collision)> {
-    if v == Vec2::ZERO {
-        return None
+/// Reflects `v` off a surface with unit normal `normal` as `v - 2(v·n)n`,
+/// scaling the normal part by `restitution` and removing the `friction`
+/// fraction of the tangential part. Velocities already leaving the surface
+/// are returned unchanged.
+pub(crate) fn reflect(v: Vec2, normal: Vec2, restitution: f32, friction: f32) -> Vec2 {
+    let vn = v.dot(normal);
+    if vn >= 0.0 {
+        return v;
     }
-    let mut nearest = None;
 
-    let x1 = Vec2::new(rect.x - rect_size.x / 2.0, rect.y - rect_size.y/2.0);
-    let x2 = Vec2::new(rect.x - rect_size.x / 2.0,rect.y + rect_size.y/2.0);
-    let x3 = Vec2::new(rect.x + rect_size.x / 2.0,rect.y + rect_size.y/2.0);
-    let x4 = Vec2::new(rect.x + rect_size.x / 2.0,rect.y - rect_size.y/2.0);
+    let normal_part = vn * normal;
+    let tangent_part = v - normal_part;
 
-    let edges = vec![
-        (x1, x2,Collision::Left), 
-        (x2, x3, Collision::Top),
-        (x3, x4, Collision::Right),
-        (x4, x1, Collision::Bottom),
-    ];
-
-    // println!("edges: {:?}", edges);
+    tangent_part * (1.0 - friction) - normal_part * restitution
+}
 
-    // let check_points: (Option<Vec2>, Option<Vec2>);
-    let point1 ;
-    if v.x > 0.0 {
-        point1 = Some(Vec2::new(circle.x + radius, circle.y));
-    } else if v.x < 0.0 {
-        point1 = Some(Vec2::new(circle.x - radius, circle.y));
+/// Sweeps two moving circles against each other.
+///
+/// The contact normal points from the second circle towards the first one.
+/// Overlapping circles that are still approaching are reported at `toi` 0.
+pub(crate) fn time_of_collide_circle_circle(c1: Vec2, r1: f32, v1: Vec2, c2: Vec2, r2: f32, v2: Vec2) -> Option<Contact> {
+    let p = c1 - c2;
+    let v = v1 - v2;
+    let radius = r1 + r2;
+
+    let toi = if p.length_squared() < radius * radius {
+        if p.dot(v) >= 0.0 {
+            return None;
+        }
+        0.0
     } else {
-        point1 = None;
+        time_of_point_circle(p, v, radius)?
+    };
+
+    let normal = (p + v * toi).normalize_or_zero();
+    if normal == Vec2::ZERO {
+        return None;
     }
 
-    let point2 ;
-    if v.y > 0.0 {
-        point2 = Some(Vec2::new(circle.x, circle.y + radius));
-    } else if v.y < 0.0 {
-        point2 = Some(Vec2::new(circle.x, circle.y - radius));
-    } else {
-        point2 = None;
-    }
-    //
-    let point3;
-    if !v.x.is_zero() && !v.y.is_zero() {
-        let angle = (v.y/v.x).abs().atan();
-        let x;
-        let y;
-        if v.x > 0.0 {
-            x = radius * angle.cos();
-        } else {
-            x = -radius * angle.cos();
-        }
+    Some(Contact {
+        toi,
+        point: c2 + v2 * toi + normal * r2,
+        normal,
+    })
+}
 
-        if v.y > 0.0 {
-            y = radius * angle.sin();
-        } else {
-            y = -radius * angle.sin();
-        }
+/// Exchanges momentum along `normal` between two circles of equal mass.
+///
+/// With `restitution` 1 the collision is perfectly elastic and the normal
+/// components of the two velocities are swapped.
+pub(crate) fn resolve_circle_circle(v1: Vec2, v2: Vec2, normal: Vec2, restitution: f32) -> (Vec2, Vec2) {
+    let vn = (v1 - v2).dot(normal);
+    if vn >= 0.0 {
+        return (v1, v2);
+    }
 
-        point3 = Some(Vec2::new(x, y));
-    } else {
-        point3 = None;
-    }
-
-
-    let check_points = vec![point1, point2, point3];
-    let mut nearest_point = None;
-
-    for point in check_points {
-        // println!("point:{:?}", point);
-        if let Some(point) = point {
-            let checked_nearest = check_collide_point_nearest_edge(point, v, &edges);
-            // println!("checked_nearest:{:?} point:{}", checked_nearest, point);
-            if let Some(checked_nearest) = checked_nearest {
-                match nearest {
-                    Some((t, _)) => {
-                        if checked_nearest.0 < t {
-                            nearest = Some(checked_nearest);
-                            nearest_point = Some(point);
-                        }
-                    }
-                    None => {
-                        nearest = Some(checked_nearest);
-                        nearest_point = Some(point);
-                    }
-                }
+    let impulse = (1.0 + restitution) / 2.0 * vn * normal;
+    (v1 - impulse, v2 + impulse)
+}
+
+/// Sweeps a circle moving inside an axis aligned rectangle and returns the first
+/// time it touches one of the rectangle's inner walls.
+///
+/// The returned normal points back into the rectangle.
+pub(crate) fn time_of_collide_circle_inside_rect(circle: Vec2, radius: f32, v: Vec2, rect: Vec2, rect_size: Vec2) -> Option<Contact> {
+    if v == Vec2::ZERO {
+        return None
+    }
+
+    let half = rect_size / 2.0 - Vec2::splat(radius);
+    let p = circle - rect;
+    let mut result = None;
+
+    if v.x != 0.0 {
+        let side = v.x.signum();
+        let t = ((side * half.x - p.x) / v.x).max(0.0);
+        result = nearest(result, Contact {
+            toi: t,
+            point: rect + Vec2::new(side * (half.x + radius), p.y + v.y * t),
+            normal: Vec2::new(-side, 0.0),
+        });
+    }
+
+    if v.y != 0.0 {
+        let side = v.y.signum();
+        let t = ((side * half.y - p.y) / v.y).max(0.0);
+        result = nearest(result, Contact {
+            toi: t,
+            point: rect + Vec2::new(p.x + v.x * t, side * (half.y + radius)),
+            normal: Vec2::new(0.0, -side),
+        });
+    }
+
+    result
+}
+
+/// Sweeps an axis aligned rectangle moving with velocity `v` against another
+/// one and returns the first time they overlap, 0 when they already do.
+pub(crate) fn time_of_collide_rect_rect(rect: Vec2, rect_size: Vec2, v: Vec2, other: Vec2, other_size: Vec2) -> Option<f32> {
+    let half = (rect_size + other_size) / 2.0;
+    let p = rect - other;
+    let mut enter = 0.0f32;
+    let mut exit = f32::INFINITY;
+
+    for (p, v, half) in [(p.x, v.x, half.x), (p.y, v.y, half.y)] {
+        if v.abs() < EPSILON {
+            if p.abs() >= half {
+                return None
             }
+            continue
         }
+        let (t1, t2) = ((-half - p) / v, (half - p) / v);
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
     }
-    // println!("===nearest point:{:?}===", nearest_point);
-    nearest
+
+    (enter < exit).then_some(enter)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::f32::consts::PI;
     use bevy::math::Vec2;
 
-    use super::{time_of_vertex_edge_parallel, time_of_collide_circle_rect};
+    use super::{
+        time_of_collide_circle_rect, time_of_collide_circle_inside_rect, penetration_circle_rect, reflect,
+        time_of_collide_circle_circle, resolve_circle_circle, time_of_collide_rect_rect,
+    };
+
+    fn assert_vec_eq(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{} != {}", a, b);
+    }
 
     #[test]
-    fn test_time_of_vetex_edge_parallel() {
-        let result = time_of_vertex_edge_parallel(
-           Vec2::new(0.0, -5.0),
-           Vec2::new(0.0,1.0),
-           Vec2::new(-1.0, 0.0),
-           Vec2::new(1.0,0.0),
-        );
-        
-        println!("result: {:?}", result);
+    fn test_time_of_collide_circle_rect() {
+        let v = Vec2::new(-9.495282,282.6833);
+        let circle = Vec2::new(27.58929-v.x*0.015625 * 4.0, 4.3467054-v.y*0.015625 * 4.0);
+        let radius = 4.0;
 
-        let mut list = vec![Vec2::ZERO,Vec2::ZERO,Vec2::ZERO];
+        let rect = Vec2::new(30.0,6.0);
+        let rect_size = Vec2::new(10.0,10.0);
 
+        let collision = time_of_collide_circle_rect(circle, radius, v, rect, rect_size).unwrap();
 
-        let mut x = 1;
+        assert_vec_eq(collision.normal, Vec2::new(0.0, -1.0));
+        let center = circle + v * collision.toi;
+        assert!((center.y - (rect.y - rect_size.y / 2.0 - radius)).abs() < 1e-3);
+    }
 
-        println!("x: {}", x);
+    #[test]
+    fn test_time_of_collide_circle_rect_edge() {
+        let contact = time_of_collide_circle_rect(
+            Vec2::new(-20.0, 2.0),
+            4.0,
+            Vec2::new(10.0, 0.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        ).unwrap();
+
+        assert!((contact.toi - 1.1).abs() < 1e-5);
+        assert_vec_eq(contact.normal, Vec2::new(-1.0, 0.0));
+        assert_vec_eq(contact.point, Vec2::new(-5.0, 2.0));
+    }
 
-        // x = 2;
+    #[test]
+    fn test_time_of_collide_circle_rect_corner() {
+        // moving diagonally straight at the top right corner
+        let contact = time_of_collide_circle_rect(
+            Vec2::new(20.0, 20.0),
+            4.0,
+            Vec2::new(-1.0, -1.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        ).unwrap();
+
+        let expected_toi = 15.0 - 4.0 / 2.0_f32.sqrt();
+        assert!((contact.toi - expected_toi).abs() < 1e-4);
+        assert_vec_eq(contact.normal, Vec2::new(1.0, 1.0).normalize());
+        assert_vec_eq(contact.point, Vec2::new(5.0, 5.0));
+    }
 
-        let y = x;
+    #[test]
+    fn test_time_of_collide_circle_rect_grazing_corner() {
+        // crosses the bounding box of the grown rectangle but stays outside the rounded corner
+        let miss = time_of_collide_circle_rect(
+            Vec2::new(-18.5, -1.5),
+            4.0,
+            Vec2::new(10.0, 10.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        );
+        assert!(miss.is_none());
+
+        let hit = time_of_collide_circle_rect(
+            Vec2::new(-20.0, 7.0),
+            4.0,
+            Vec2::new(10.0, 0.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        ).unwrap();
+        assert!(hit.normal.x < 0.0 && hit.normal.y > 0.0);
+        assert_vec_eq(hit.point, Vec2::new(-5.0, 5.0));
+    }
 
-        println!("x: {}", x);
+    #[test]
+    fn test_time_of_collide_circle_rect_moving_away() {
+        let contact = time_of_collide_circle_rect(
+            Vec2::new(-20.0, 0.0),
+            4.0,
+            Vec2::new(-10.0, 3.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        );
+        assert!(contact.is_none());
     }
 
     #[test]
-    fn test_time_of_collide_circle_rect() {
-        let v = Vec2::new(-9.495282,282.6833);
-        let circle = Vec2::new(27.58929-v.x*0.015625 * 4.0, 4.3467054-v.y*0.015625 * 4.0);
-        let radius = 4.0;
+    fn test_time_of_collide_circle_inside_rect() {
+        let contact = time_of_collide_circle_inside_rect(
+            Vec2::new(0.0, 0.0),
+            4.0,
+            Vec2::new(10.0, 20.0),
+            Vec2::ZERO,
+            Vec2::new(100.0, 100.0),
+        ).unwrap();
+
+        assert!((contact.toi - 2.3).abs() < 1e-5);
+        assert_vec_eq(contact.normal, Vec2::new(0.0, -1.0));
+        assert_vec_eq(contact.point, Vec2::new(23.0, 50.0));
+    }
 
-        let rect = Vec2::new(30.0,6.0);
-        let rect_size = Vec2::new(10.0,10.0);
+    #[test]
+    fn test_time_of_collide_circle_rect_inside() {
+        // centre inside the rectangle, closest to the right edge
+        let contact = time_of_collide_circle_rect(
+            Vec2::new(3.0, 1.0),
+            4.0,
+            Vec2::new(-10.0, 0.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        ).unwrap();
+        assert_eq!(contact.toi, 0.0);
+        assert_vec_eq(contact.normal, Vec2::new(1.0, 0.0));
+
+        // already leaving, nothing to resolve
+        let leaving = time_of_collide_circle_rect(
+            Vec2::new(3.0, 1.0),
+            4.0,
+            Vec2::new(10.0, 0.0),
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        );
+        assert!(leaving.is_none());
+    }
 
+    #[test]
+    fn test_penetration_circle_rect() {
+        let contact = penetration_circle_rect(
+            Vec2::new(7.0, 7.0),
+            4.0,
+            Vec2::ZERO,
+            Vec2::new(10.0, 10.0),
+        ).unwrap();
+        assert_vec_eq(contact.normal, Vec2::new(1.0, 1.0).normalize());
+        assert_vec_eq(contact.point, Vec2::new(5.0, 5.0));
+
+        assert!(penetration_circle_rect(Vec2::new(9.0, 9.0), 4.0, Vec2::ZERO, Vec2::new(10.0, 10.0)).is_none());
+    }
 
-        let collision = time_of_collide_circle_rect(circle, radius, v, rect, rect_size);
+    #[test]
+    fn test_reflect() {
+        let v = Vec2::new(3.0, -4.0);
+        assert_vec_eq(reflect(v, Vec2::Y, 1.0, 0.0), Vec2::new(3.0, 4.0));
+        assert_vec_eq(reflect(v, Vec2::Y, 0.5, 0.5), Vec2::new(1.5, 2.0));
 
-        println!("result:{:?}", collision);
+        let n = Vec2::new(1.0, 1.0).normalize();
+        let reflected = reflect(Vec2::new(-1.0, -1.0), n, 1.0, 0.0);
+        assert_vec_eq(reflected, Vec2::new(1.0, 1.0));
 
-        let tan :f32 =-1.0;
-        println!("tan:{} {}", tan.atan(), PI/4.0);
+        // moving away from the surface keeps its velocity
+        assert_vec_eq(reflect(v, -Vec2::Y, 1.0, 0.5), v);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_time_of_collide_circle_circle() {
+        // head on, closing at 20 per second with 10 between the surfaces
+        let contact = time_of_collide_circle_circle(
+            Vec2::new(-9.0, 0.0), 4.0, Vec2::new(10.0, 0.0),
+            Vec2::new(9.0, 0.0), 4.0, Vec2::new(-10.0, 0.0),
+        ).unwrap();
+        assert!((contact.toi - 0.5).abs() < 1e-5);
+        assert_vec_eq(contact.normal, Vec2::new(-1.0, 0.0));
+        assert_vec_eq(contact.point, Vec2::ZERO);
+
+        // parallel movement never meets
+        assert!(time_of_collide_circle_circle(
+            Vec2::new(0.0, 0.0), 4.0, Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 20.0), 4.0, Vec2::new(10.0, 0.0),
+        ).is_none());
+
+        // overlapping but already separating
+        assert!(time_of_collide_circle_circle(
+            Vec2::new(-1.0, 0.0), 4.0, Vec2::new(-10.0, 0.0),
+            Vec2::new(1.0, 0.0), 4.0, Vec2::new(10.0, 0.0),
+        ).is_none());
+    }
+
+    #[test]
+    fn test_resolve_circle_circle() {
+        let (v1, v2) = resolve_circle_circle(Vec2::new(10.0, 3.0), Vec2::new(-10.0, 0.0), Vec2::new(-1.0, 0.0), 1.0);
+        assert_vec_eq(v1, Vec2::new(-10.0, 3.0));
+        assert_vec_eq(v2, Vec2::new(10.0, 0.0));
+
+        // total momentum is kept
+        let (v1, v2) = resolve_circle_circle(Vec2::new(5.0, 1.0), Vec2::new(-2.0, 4.0), Vec2::new(-0.6, -0.8), 0.5);
+        assert_vec_eq(v1 + v2, Vec2::new(3.0, 5.0));
+    }
+
+    #[test]
+    fn test_time_of_collide_rect_rect() {
+        // 8 between the edges, closing at 100 per second
+        let toi = time_of_collide_rect_rect(Vec2::ZERO, Vec2::new(2.0, 10.0), Vec2::new(0.0, 100.0), Vec2::new(0.0, 17.0), Vec2::new(8.0, 8.0)).unwrap();
+        assert!((toi - 0.08).abs() < 1e-5);
+
+        // already overlapping
+        assert_eq!(time_of_collide_rect_rect(Vec2::ZERO, Vec2::ONE, Vec2::new(0.0, 100.0), Vec2::new(0.5, 0.0), Vec2::ONE), Some(0.0));
+
+        // passing beside it, or moving away
+        assert!(time_of_collide_rect_rect(Vec2::ZERO, Vec2::ONE, Vec2::new(0.0, 100.0), Vec2::new(2.0, 10.0), Vec2::ONE).is_none());
+        assert!(time_of_collide_rect_rect(Vec2::ZERO, Vec2::ONE, Vec2::new(0.0, -100.0), Vec2::new(0.0, 10.0), Vec2::ONE).is_none());
+    }
+}
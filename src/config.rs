@@ -0,0 +1,364 @@
+use bevy::{prelude::*, asset::AssetEvent};
+use serde::{Serialize, Deserialize};
+
+use crate::{effect::{Effect, Stacking}, json_plugin::JsonAssetPlugin, paddle::PaddleReflection};
+
+pub const BACKGROUND_COLOR: Color = Color::rgb(35.0/255.0, 35.0/255.0, 105.0/255.0);
+pub const EDGE_COLOR: Color = Color::rgb(25.0/255.0, 25.0/255.0, 72.0/255.0);
+pub const PADDLE_COLOR: Color = Color::WHITE;
+pub const BALL_COLOR: Color = Color::WHITE;
+pub const LASER_COLOR: Color = Color::rgb(1.0, 0.2, 0.6);
+pub const SHIELD_COLOR: Color = Color::rgb(0.4, 0.8, 1.0);
+
+pub const HEADLESS_DEFAULT_TICKS: u32 = 64 * 60;
+pub const HEADLESS_LOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Where the [`GameConfig`] is loaded from, relative to the assets directory.
+pub const GAME_CONFIG_PATH: &str = "config/game.config.json";
+/// The [`Campaign`](crate::level::Campaign) played from the start.
+pub const CAMPAIGN_PATH: &str = "levels/main.campaign.json";
+/// The [`BrickTypeRegistry`](crate::brick::BrickTypeRegistry) levels are loaded with.
+pub const BRICK_TYPES_PATH: &str = "levels/bricks.bricktypes.json";
+/// The [`DropTables`](crate::drop_table::DropTables) rewards drop from.
+pub const DROP_TABLES_PATH: &str = "rewards/rewards.droptables.json";
+/// The [`RewardRegistry`](crate::reward::RewardRegistry) drop tables name rewards from.
+pub const REWARDS_PATH: &str = "rewards/main.rewards.json";
+/// Where the [`HighScores`](crate::high_score::HighScores) are saved, relative
+/// to the user data directory.
+pub const HIGH_SCORES_PATH: &str = "breakout/high_scores.json";
+
+/// Every tuning value of the game.
+///
+/// Starts from its defaults and is replaced by `assets/config/game.config.json`
+/// once loaded, then again every time the file changes when bevy's
+/// `file_watcher` feature is on. Missing fields keep their default.
+#[derive(Resource, Asset, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub screen_size: Vec2,
+    /// Size of the arena the balls bounce in, centered on the origin.
+    pub edge_size: Vec2,
+    /// Seconds between clearing a level and loading the next one.
+    pub level_transition: f32,
+    /// Balls that can be lost before the game is over.
+    pub lives: u32,
+    pub paddle: PaddleConfig,
+    pub ball: BallConfig,
+    pub brick: BrickConfig,
+    pub reward: RewardConfig,
+    pub effects: EffectsConfig,
+    pub score: ScoreConfig,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            screen_size: Vec2::new(720.0, 960.0),
+            edge_size: Vec2::new(680.0, 900.0),
+            level_transition: 1.0,
+            lives: 3,
+            paddle: PaddleConfig::default(),
+            ball: BallConfig::default(),
+            brick: BrickConfig::default(),
+            reward: RewardConfig::default(),
+            effects: EffectsConfig::default(),
+            score: ScoreConfig::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Half the arena size, i.e. the right and top edges.
+    pub fn half_edge_size(&self) -> Vec2 {
+        self.edge_size / 2.0
+    }
+}
+
+/// `min_bounce_angle` is the flattest angle above the horizontal in degrees a
+/// ball may leave the paddle at.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaddleConfig {
+    pub size: Vec2,
+    pub start: Vec2,
+    pub speed: f32,
+    pub reflection: PaddleReflection,
+    pub min_bounce_angle: f32,
+}
+
+impl Default for PaddleConfig {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(80.0, 10.0),
+            start: Vec2::new(0.0, -200.0),
+            speed: 400.0,
+            reflection: PaddleReflection::default(),
+            min_bounce_angle: 20.0,
+        }
+    }
+}
+
+/// How balls move and bounce off bricks and edges.
+///
+/// `restitution` scales the velocity along the contact normal and `friction`
+/// removes that fraction of the velocity along the surface. When
+/// `ball_collisions` is off balls pass through each other.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BallConfig {
+    pub speed: f32,
+    pub radius: f32,
+    /// Seconds a served ball waits on the paddle before it launches on its own.
+    pub auto_launch: f32,
+    pub max_count: i32,
+    pub max_bounces_per_tick: usize,
+    pub restitution: f32,
+    pub friction: f32,
+    pub ball_collisions: bool,
+}
+
+impl Default for BallConfig {
+    fn default() -> Self {
+        Self {
+            speed: 200.0,
+            radius: 4.0,
+            auto_launch: 3.0,
+            max_count: 5000,
+            max_bounces_per_tick: 8,
+            restitution: 1.0,
+            friction: 0.0,
+            ball_collisions: false,
+        }
+    }
+}
+
+impl BallConfig {
+    pub fn hash_cell_size(&self) -> Vec2 {
+        Vec2::splat(self.radius * 4.0)
+    }
+}
+
+/// `grid_cell_bricks` is how many bricks fit along each side of a cell of the
+/// collision grid.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrickConfig {
+    pub size: Vec2,
+    pub gap: f32,
+    pub grid_cell_bricks: Vec2,
+}
+
+impl Default for BrickConfig {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(10.0, 10.0),
+            gap: 2.0,
+            grid_cell_bricks: Vec2::new(4.0, 4.0),
+        }
+    }
+}
+
+impl BrickConfig {
+    /// A brick and the gap around it, the area a brick blocks.
+    pub fn collider_size(&self) -> Vec2 {
+        self.size + Vec2::splat(self.gap)
+    }
+
+    pub fn grid_cell_size(&self) -> Vec2 {
+        self.grid_cell_bricks * self.collider_size()
+    }
+}
+
+/// Falling rewards. `drop_table` is the drop table of the bricks whose type
+/// and level do not name one, see [`DropTables`](crate::drop_table::DropTables).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RewardConfig {
+    pub drop_table: String,
+    pub size: Vec2,
+    pub fall_speed: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        Self {
+            drop_table: "default".to_string(),
+            size: Vec2::new(20.0, 35.0),
+            fall_speed: 200.0,
+        }
+    }
+}
+
+/// Bonus points. Every `combo_step` bricks broken in a row without a ball
+/// touching the paddle raise the multiplier of the brick points by one, up to
+/// `max_multiplier`. A blast breaking several bricks is worth
+/// `explosion_bonus` for each brick past the first. Clearing a level in less
+/// than `par_time` seconds, unless the level sets its own, is worth
+/// `time_bonus` per second left. `high_scores` is how many scores each high
+/// score table keeps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreConfig {
+    pub combo_step: u32,
+    pub max_multiplier: u32,
+    pub explosion_bonus: i32,
+    pub par_time: f32,
+    pub time_bonus: i32,
+    pub high_scores: usize,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            combo_step: 5,
+            max_multiplier: 4,
+            explosion_bonus: 2,
+            par_time: 120.0,
+            time_bonus: 5,
+            high_scores: 10,
+        }
+    }
+}
+
+/// How long each [`Effect`] lasts and how it stacks, and the lasers shot
+/// during [`Effect::LaserPaddle`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectsConfig {
+    pub wide_paddle: EffectConfig,
+    pub narrow_paddle: EffectConfig,
+    pub slow_ball: EffectConfig,
+    pub fast_ball: EffectConfig,
+    pub sticky_paddle: EffectConfig,
+    pub laser_paddle: EffectConfig,
+    pub fireball: EffectConfig,
+    pub shield: EffectConfig,
+    pub laser_speed: f32,
+    pub laser_size: Vec2,
+}
+
+impl EffectsConfig {
+    pub fn get(&self, effect: Effect) -> &EffectConfig {
+        match effect {
+            Effect::WidePaddle => &self.wide_paddle,
+            Effect::NarrowPaddle => &self.narrow_paddle,
+            Effect::SlowBall => &self.slow_ball,
+            Effect::FastBall => &self.fast_ball,
+            Effect::StickyPaddle => &self.sticky_paddle,
+            Effect::LaserPaddle => &self.laser_paddle,
+            Effect::Fireball => &self.fireball,
+            Effect::Shield => &self.shield,
+        }
+    }
+}
+
+impl Default for EffectsConfig {
+    fn default() -> Self {
+        Self {
+            wide_paddle: EffectConfig::new(10.0, Stacking::Stack, 2, 1.5),
+            narrow_paddle: EffectConfig::new(10.0, Stacking::Refresh, 1, 0.6),
+            slow_ball: EffectConfig::new(8.0, Stacking::Extend, 1, 0.7),
+            fast_ball: EffectConfig::new(8.0, Stacking::Refresh, 1, 1.4),
+            sticky_paddle: EffectConfig::new(10.0, Stacking::Extend, 1, 1.0),
+            laser_paddle: EffectConfig::new(8.0, Stacking::Extend, 1, 3.0),
+            fireball: EffectConfig::new(6.0, Stacking::Extend, 1, 1.0),
+            shield: EffectConfig::new(15.0, Stacking::Extend, 1, 1.0),
+            laser_speed: 600.0,
+            laser_size: Vec2::new(2.0, 8.0),
+        }
+    }
+}
+
+/// An effect lasts `duration` seconds, `strength` scales the paddle width or
+/// the ball speed for the paddle and ball effects, once per stack, and is the
+/// number of shots per second for the lasers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectConfig {
+    pub duration: f32,
+    pub stacking: Stacking,
+    pub max_stacks: u32,
+    pub strength: f32,
+}
+
+impl EffectConfig {
+    pub fn new(duration: f32, stacking: Stacking, max_stacks: u32, strength: f32) -> Self {
+        Self {
+            duration,
+            stacking,
+            max_stacks,
+            strength,
+        }
+    }
+}
+
+impl Default for EffectConfig {
+    fn default() -> Self {
+        Self::new(10.0, Stacking::Refresh, 1, 1.0)
+    }
+}
+
+#[derive(Resource)]
+struct GameConfigHandle(Handle<GameConfig>);
+
+/// Loads the [`GameConfig`] and keeps the resource in step with the asset.
+pub(crate) struct GameConfigPlugin;
+
+impl Plugin for GameConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<GameConfig>::new(&["config.json"]))
+            .init_resource::<GameConfig>()
+            .add_systems(Startup, load_game_config)
+            .add_systems(PreUpdate, update_game_config);
+    }
+}
+
+fn load_game_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameConfigHandle(asset_server.load(GAME_CONFIG_PATH)));
+}
+
+fn update_game_config(
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    handle: Option<Res<GameConfigHandle>>,
+    assets: Res<Assets<GameConfig>>,
+    mut config: ResMut<GameConfig>,
+) {
+    let Some(handle) = handle else {
+        return
+    };
+
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(loaded) = assets.get(*id) {
+                    info!("game config {}", if matches!(event, AssetEvent::Added { .. }) { "loaded" } else { "reloaded" });
+                    // only touch the resource on actual changes, systems react to it being changed
+                    if *config != *loaded {
+                        *config = loaded.clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameConfig;
+
+    #[test]
+    fn test_missing_fields_keep_defaults() {
+        let config: GameConfig = serde_json::from_str(r#"{ "ball": { "speed": 300.0 } }"#).unwrap();
+
+        assert_eq!(config.ball.speed, 300.0);
+        assert_eq!(config.ball.radius, GameConfig::default().ball.radius);
+        assert_eq!(config.paddle, GameConfig::default().paddle);
+    }
+
+    #[test]
+    fn test_shipped_config_matches_defaults() {
+        let config: GameConfig = serde_json::from_str(include_str!("../assets/config/game.config.json")).unwrap();
+        assert_eq!(config, GameConfig::default());
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::{Rng, distributions::{Distribution, WeightedIndex}};
+use serde::{Serialize, Deserialize};
+
+/// The reward drop tables, by name.
+///
+/// Read from [`DROP_TABLES_PATH`](crate::config::DROP_TABLES_PATH). A broken
+/// brick rolls the table of its [`BrickType`](crate::brick::BrickType) if it
+/// has one, else the table of the level, else
+/// [`RewardConfig::drop_table`](crate::config::RewardConfig::drop_table).
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct DropTables {
+    pub tables: HashMap<String, DropTableData>,
+}
+
+impl DropTables {
+    pub fn get(&self, name: &str) -> Option<&DropTableData> {
+        self.tables.get(name)
+    }
+}
+
+/// A broken brick drops a reward with probability `chance`, and at the latest
+/// every `pity` broken bricks when `pity` is not 0. Nothing drops while
+/// `max_falling` rewards of the table are still falling, unless it is 0.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DropTableData {
+    pub chance: f32,
+    pub pity: u32,
+    pub max_falling: u32,
+    pub entries: Vec<DropEntry>,
+}
+
+impl Default for DropTableData {
+    fn default() -> Self {
+        Self {
+            chance: 0.2,
+            pity: 0,
+            max_falling: 0,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// A reward of a drop table, by its name in the
+/// [`RewardRegistry`](crate::reward::RewardRegistry), picked in proportion to
+/// its `weight` among the entries not dropped in the last `cooldown` seconds.
+/// When set, the parameter of the reward is drawn from the inclusive range
+/// `param`, see [`RewardKind::with_param`](crate::reward::RewardKind::with_param).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DropEntry {
+    pub reward: String,
+    pub weight: u32,
+    pub cooldown: f32,
+    pub param: Option<[u32; 2]>,
+}
+
+impl Default for DropEntry {
+    fn default() -> Self {
+        Self {
+            reward: String::new(),
+            weight: 1,
+            cooldown: 0.0,
+            param: None,
+        }
+    }
+}
+
+/// What a drop table remembers between rolls: the bricks broken since its
+/// last drop and when each of its rewards last dropped.
+#[derive(Default, Debug)]
+pub struct DropTableState {
+    pub dry: u32,
+    pub last_drop: HashMap<String, f32>,
+}
+
+impl DropTableData {
+    /// Rolls the table for a brick broken at `now` seconds while `falling` of
+    /// its rewards are falling, returns the name of the reward dropped and its
+    /// parameter.
+    pub fn roll(&self, state: &mut DropTableState, now: f32, falling: u32, rng: &mut impl Rng) -> Option<(&str, Option<u32>)> {
+        if self.max_falling > 0 && falling >= self.max_falling {
+            return None
+        }
+
+        let pity = self.pity > 0 && state.dry + 1 >= self.pity;
+        if !pity && !rng.gen_bool(self.chance.clamp(0.0, 1.0) as f64) {
+            state.dry += 1;
+            return None
+        }
+
+        let available: Vec<&DropEntry> = self.entries.iter()
+            .filter(|entry| entry.weight > 0)
+            .filter(|entry| state.last_drop.get(&entry.reward).is_none_or(|&last| now - last >= entry.cooldown))
+            .collect();
+        let Ok(weights) = WeightedIndex::new(available.iter().map(|entry| entry.weight)) else {
+            // everything is cooling down, the pity still counts
+            state.dry += 1;
+            return None
+        };
+
+        let entry = available[weights.sample(rng)];
+        state.dry = 0;
+        state.last_drop.insert(entry.reward.clone(), now);
+        let param = entry.param.map(|[a, b]| rng.gen_range(a.min(b)..=a.max(b)));
+        Some((&entry.reward, param))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::{DropEntry, DropTableData, DropTableState, DropTables};
+    use crate::{brick::BrickTypeRegistry, config::RewardConfig, reward::RewardRegistry};
+
+    fn entry(reward: &str, cooldown: f32) -> DropEntry {
+        DropEntry {
+            reward: reward.to_string(),
+            cooldown,
+            param: Some([2, 4]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pity_and_max_falling() {
+        let table = DropTableData {
+            chance: 0.0,
+            pity: 3,
+            max_falling: 1,
+            entries: vec![entry("split", 0.0)],
+        };
+        let mut state = DropTableState::default();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert_eq!(table.roll(&mut state, 0.0, 0, &mut rng), None);
+        assert_eq!(table.roll(&mut state, 0.0, 0, &mut rng), None);
+        let (reward, param) = table.roll(&mut state, 0.0, 0, &mut rng).expect("the pity drops on the third brick");
+        assert_eq!(reward, "split");
+        assert!((2..=4).contains(&param.unwrap()));
+        assert_eq!(state.dry, 0);
+
+        state.dry = 5;
+        assert_eq!(table.roll(&mut state, 0.0, 1, &mut rng), None, "one reward is already falling");
+        assert_eq!(state.dry, 5);
+    }
+
+    #[test]
+    fn test_cooldowns() {
+        let table = DropTableData {
+            chance: 1.0,
+            entries: vec![entry("split", 5.0), entry("serve", 5.0)],
+            ..Default::default()
+        };
+        let mut state = DropTableState::default();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let first = table.roll(&mut state, 0.0, 0, &mut rng).unwrap().0.to_string();
+        let second = table.roll(&mut state, 1.0, 0, &mut rng).unwrap().0.to_string();
+        assert_ne!(first, second, "the first reward is cooling down");
+        assert_eq!(table.roll(&mut state, 2.0, 0, &mut rng), None);
+        let third = table.roll(&mut state, 5.0, 0, &mut rng).unwrap().0;
+        assert_eq!(third, first);
+    }
+
+    #[test]
+    fn test_shipped_drop_tables() {
+        let tables: DropTables = serde_json::from_str(include_str!("../assets/rewards/rewards.droptables.json")).unwrap();
+        let registry: BrickTypeRegistry = serde_json::from_str(include_str!("../assets/levels/bricks.bricktypes.json")).unwrap();
+        let rewards: RewardRegistry = serde_json::from_str(include_str!("../assets/rewards/main.rewards.json")).unwrap();
+
+        assert!(tables.get(&RewardConfig::default().drop_table).is_some());
+        for (name, table) in &tables.tables {
+            for entry in &table.entries {
+                assert!(rewards.get(&entry.reward).is_some(), "drop table {} drops a missing reward {}", name, entry.reward);
+            }
+        }
+        for (name, brick_type) in &registry.types {
+            if let Some(table) = &brick_type.drop_table {
+                assert!(tables.get(table).is_some(), "brick type {} drops from a missing table {}", name, table);
+            }
+        }
+    }
+}
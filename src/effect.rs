@@ -0,0 +1,485 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    AppState, GameSet, Velocity,
+    ball::CollisionEvent,
+    brick::{DropTable, Explosions, Explosive},
+    collide,
+    config::{EffectsConfig, GameConfig, LASER_COLOR, SHIELD_COLOR},
+    grid::BrickGrid,
+    level::{Brick, WallBlock},
+    paddle::Paddle,
+    reward::{ReceiveRewardEvent, RewardKind},
+};
+
+/// Timed power-ups, started by catching rewards and applied while they last.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Effect {
+    WidePaddle,
+    NarrowPaddle,
+    SlowBall,
+    FastBall,
+    /// Balls landing on the paddle are held on it until launched.
+    StickyPaddle,
+    /// The paddle shoots lasers breaking the bricks above it.
+    LaserPaddle,
+    /// Balls deal fireball damage and go through the bricks they break.
+    Fireball,
+    /// The bottom of the arena bounces the balls back.
+    Shield,
+}
+
+impl Effect {
+    /// The effect ended when this one starts.
+    pub fn opposite(self) -> Option<Effect> {
+        match self {
+            Effect::WidePaddle => Some(Effect::NarrowPaddle),
+            Effect::NarrowPaddle => Some(Effect::WidePaddle),
+            Effect::SlowBall => Some(Effect::FastBall),
+            Effect::FastBall => Some(Effect::SlowBall),
+            _ => None,
+        }
+    }
+}
+
+/// What happens when an effect already running starts again.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stacking {
+    /// Its timer starts over.
+    #[default]
+    Refresh,
+    /// Its duration is added to the time left.
+    Extend,
+    /// It applies once more, up to its `max_stacks`, and its timer starts over.
+    Stack,
+}
+
+/// A running effect: how many times it applies and the time it has left.
+#[derive(Clone, Debug)]
+pub struct ActiveEffect {
+    pub stacks: u32,
+    pub timer: Timer,
+}
+
+/// The effects running and the time they have left.
+///
+/// Only changes when an effect starts or ends, so systems depending on the
+/// effects can run on `resource_changed::<ActiveEffects>()`.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveEffects(HashMap<Effect, ActiveEffect>);
+
+impl ActiveEffects {
+    /// Starts `effect` or, when it is already running, applies its stacking rule.
+    /// The effect's opposite ends, it is returned when it was running.
+    pub fn start(&mut self, effect: Effect, config: &EffectsConfig) -> Option<Effect> {
+        let settings = config.get(effect);
+        let ended = effect.opposite().filter(|opposite| self.0.remove(opposite).is_some());
+
+        let duration = Duration::from_secs_f32(settings.duration.max(0.0));
+        let Some(active) = self.0.get_mut(&effect) else {
+            self.0.insert(effect, ActiveEffect {
+                stacks: 1,
+                timer: Timer::new(duration, TimerMode::Once),
+            });
+            return ended
+        };
+
+        match settings.stacking {
+            Stacking::Refresh => active.timer = Timer::new(duration, TimerMode::Once),
+            Stacking::Extend => {
+                let extended = active.timer.duration() + duration;
+                active.timer.set_duration(extended);
+            }
+            Stacking::Stack => {
+                active.stacks = (active.stacks + 1).min(settings.max_stacks.max(1));
+                active.timer = Timer::new(duration, TimerMode::Once);
+            }
+        }
+        ended
+    }
+
+    pub fn is_active(&self, effect: Effect) -> bool {
+        self.0.contains_key(&effect)
+    }
+
+    /// The time `effect` has left, `None` when it is not running.
+    pub fn remaining(&self, effect: Effect) -> Option<Duration> {
+        self.0.get(&effect).map(|active| active.timer.remaining())
+    }
+
+    /// The running effects, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Effect, &ActiveEffect)> {
+        self.0.iter().map(|(&effect, active)| (effect, active))
+    }
+
+    /// How many times `effect` applies, 0 when it is not running.
+    pub fn stacks(&self, effect: Effect) -> u32 {
+        self.0.get(&effect).map_or(0, |active| active.stacks)
+    }
+
+    /// The effect's strength raised to its stacks, 1 when it is not running.
+    pub fn factor(&self, effect: Effect, config: &EffectsConfig) -> f32 {
+        config.get(effect).strength.powi(self.stacks(effect) as i32)
+    }
+
+    /// Width of the paddle with the paddle effects applied.
+    pub fn paddle_width(&self, config: &GameConfig) -> f32 {
+        config.paddle.size.x
+            * self.factor(Effect::WidePaddle, &config.effects)
+            * self.factor(Effect::NarrowPaddle, &config.effects)
+    }
+
+    /// Speed of the balls with the ball effects applied.
+    pub fn ball_speed(&self, config: &GameConfig) -> f32 {
+        config.ball.speed
+            * self.factor(Effect::SlowBall, &config.effects)
+            * self.factor(Effect::FastBall, &config.effects)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Sent when an effect starts, or starts again while running.
+#[derive(Event, Deref, Debug, Clone, Copy)]
+pub struct EffectStartedEvent(pub Effect);
+
+/// Sent when an effect runs out, is ended by its opposite or ends with the
+/// level.
+#[derive(Event, Deref, Debug, Clone, Copy)]
+pub struct EffectEndedEvent(pub Effect);
+
+/// A shot of the [`Effect::LaserPaddle`].
+#[derive(Component)]
+pub struct Laser;
+
+/// The bottom edge drawn while [`Effect::Shield`] runs.
+#[derive(Component)]
+pub struct Shield;
+
+/// Starts the effects of the rewards caught, runs the [`ActiveEffects`] down
+/// while a level is played, and the lasers and shield they bring. Effects end
+/// with the level. Every start and end is announced by an
+/// [`EffectStartedEvent`] or [`EffectEndedEvent`].
+pub struct EffectPlugin;
+
+impl Plugin for EffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveEffects>()
+            .add_event::<ReceiveRewardEvent>()
+            .add_event::<EffectStartedEvent>()
+            .add_event::<EffectEndedEvent>()
+            .add_systems(Update, (
+                start_reward_effects,
+                tick_effects.run_if(in_state(AppState::Level)).after(start_reward_effects),
+                show_shield.run_if(resource_changed::<ActiveEffects>()),
+            ))
+            .add_systems(OnExit(AppState::Level), clear_effects)
+            .add_systems(FixedUpdate, (
+                fire_lasers.in_set(GameSet::Movement),
+                check_laser_hits.in_set(GameSet::BallCollision),
+            ));
+    }
+}
+
+fn start_reward_effects(
+    mut receive_reward_events: EventReader<ReceiveRewardEvent>,
+    mut effects: ResMut<ActiveEffects>,
+    config: Res<GameConfig>,
+    mut started_events: EventWriter<EffectStartedEvent>,
+    mut ended_events: EventWriter<EffectEndedEvent>,
+) {
+    for event in receive_reward_events.read() {
+        if let RewardKind::Effect(effect) = event.0 {
+            if let Some(opposite) = effects.start(effect, &config.effects) {
+                ended_events.send(EffectEndedEvent(opposite));
+            }
+            started_events.send(EffectStartedEvent(effect));
+        }
+    }
+}
+
+fn tick_effects(mut effects: ResMut<ActiveEffects>, time: Res<Time>, mut ended_events: EventWriter<EffectEndedEvent>) {
+    let mut expired = false;
+    // ticking alone is not a change, see ActiveEffects
+    effects.bypass_change_detection().0.retain(|&effect, active| {
+        if active.timer.tick(time.delta()).finished() {
+            debug!("effect {:?} expired", effect);
+            ended_events.send(EffectEndedEvent(effect));
+            expired = true;
+        }
+        !active.timer.finished()
+    });
+
+    if expired {
+        effects.set_changed();
+    }
+}
+
+fn clear_effects(
+    mut commands: Commands,
+    mut effects: ResMut<ActiveEffects>,
+    laser_query: Query<Entity, With<Laser>>,
+    mut ended_events: EventWriter<EffectEndedEvent>,
+) {
+    ended_events.send_batch(effects.iter().map(|(effect, _)| EffectEndedEvent(effect)));
+    effects.clear();
+    for entity in &laser_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn show_shield(
+    mut commands: Commands,
+    shield_query: Query<Entity, With<Shield>>,
+    effects: Res<ActiveEffects>,
+    config: Res<GameConfig>,
+) {
+    let active = effects.is_active(Effect::Shield);
+    if active && shield_query.is_empty() {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: SHIELD_COLOR,
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, -config.half_edge_size().y, 1.0)
+                    .with_scale(Vec3::new(config.edge_size.x, 4.0, 0.0)),
+                ..default()
+            },
+            Shield,
+        ));
+    } else if !active {
+        for entity in &shield_query {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Shoots a pair of lasers from the ends of the paddle, `strength` times a
+/// second while [`Effect::LaserPaddle`] runs.
+fn fire_lasers(
+    mut commands: Commands,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    effects: Res<ActiveEffects>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut reload: Local<f32>,
+) {
+    let settings = config.effects.get(Effect::LaserPaddle);
+    let Ok(paddle) = paddle_query.get_single() else {
+        return
+    };
+    if !effects.is_active(Effect::LaserPaddle) || settings.strength <= 0.0 {
+        *reload = 0.0;
+        return
+    }
+
+    *reload -= time.delta_seconds();
+    if *reload > 0.0 {
+        return
+    }
+    *reload += 1.0 / settings.strength;
+
+    let size = config.effects.laser_size;
+    for side in [-1.0, 1.0] {
+        let x = paddle.translation.x + side * (paddle.scale.x - size.x) / 2.0;
+        let y = paddle.translation.y + (paddle.scale.y + size.y) / 2.0;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: LASER_COLOR,
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, 0.0).with_scale(size.extend(0.0)),
+                ..default()
+            },
+            Laser,
+            Velocity(Vec2::new(0.0, config.effects.laser_speed)),
+        ));
+    }
+}
+
+/// Stops every laser at the first brick or wall it reaches, taking one hit
+/// point off bricks, and removes the lasers leaving the arena. Lasers are swept
+/// over the whole of the tick's movement, so fast ones do not skip bricks.
+fn check_laser_hits(
+    mut commands: Commands,
+    laser_query: Query<(Entity, &Transform, &Velocity), With<Laser>>,
+    mut brick_query: Query<(&Transform, AnyOf<(&mut Brick, &WallBlock)>, Option<&Explosive>, Option<&DropTable>), Without<Laser>>,
+    grid: Res<BrickGrid>,
+    mut explosions: ResMut<Explosions>,
+    mut candidates: Local<Vec<Entity>>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut collision_events: EventWriter<CollisionEvent>,
+) {
+    let delta = time.delta_seconds();
+    let brick_size = config.brick.collider_size();
+    for (laser, transform, velocity) in &laser_query {
+        let size = transform.scale.truncate();
+        let pos = transform.translation.truncate();
+        if pos.y - size.y / 2.0 > config.half_edge_size().y {
+            commands.entity(laser).despawn();
+            continue
+        }
+
+        // where it was at the start of the tick, apply_velocity moved it since
+        let previous = pos - velocity.0 * delta;
+        grid.query_aabb(pos.min(previous) - size / 2.0, pos.max(previous) + size / 2.0, &mut candidates);
+        let mut hit: Option<(Entity, f32)> = None;
+        for &entity in candidates.iter() {
+            let Ok((brick_transform, (brick, _), _, _)) = brick_query.get(entity) else {
+                continue
+            };
+            if brick.is_some_and(|brick| brick.destroy) {
+                continue
+            }
+            let brick_pos = brick_transform.translation.truncate();
+            let Some(toi) = collide::time_of_collide_rect_rect(previous, size, velocity.0, brick_pos, brick_size) else {
+                continue
+            };
+            if toi <= delta && hit.is_none_or(|(_, first)| toi < first) {
+                hit = Some((entity, toi));
+            }
+        }
+
+        let Some((entity, _)) = hit else {
+            continue
+        };
+        commands.entity(laser).despawn();
+
+        let (brick_transform, (brick, _), explosive, drop_table) = brick_query.get_mut(entity).unwrap();
+        if let Some(mut brick) = brick {
+            if brick.damage(1) {
+                let brick_pos = brick_transform.translation.truncate();
+                commands.entity(entity).despawn();
+                collision_events.send(CollisionEvent {
+                    pos: brick_pos,
+                    score: brick.score,
+                    drop_table: drop_table.map(|table| table.0.clone()),
+                });
+                if let Some(explosive) = explosive {
+                    explosions.push(brick_pos, explosive.0);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{check_laser_hits, tick_effects, ActiveEffects, Effect, EffectEndedEvent, Laser, Stacking};
+    use crate::{
+        Velocity,
+        ball::CollisionEvent,
+        brick::Explosions,
+        config::{EffectsConfig, GameConfig},
+        grid::BrickGrid,
+        level::Brick,
+    };
+
+    #[test]
+    fn test_stacking_rules() {
+        let mut config = EffectsConfig::default();
+        config.wide_paddle.stacking = Stacking::Stack;
+        config.wide_paddle.max_stacks = 2;
+        config.slow_ball.stacking = Stacking::Extend;
+        config.fast_ball.stacking = Stacking::Refresh;
+
+        let mut effects = ActiveEffects::default();
+        for _ in 0..3 {
+            effects.start(Effect::WidePaddle, &config);
+        }
+        assert_eq!(effects.stacks(Effect::WidePaddle), 2);
+        let strength = config.wide_paddle.strength;
+        assert_eq!(effects.factor(Effect::WidePaddle, &config), strength * strength);
+
+        assert_eq!(effects.start(Effect::NarrowPaddle, &config), Some(Effect::WidePaddle));
+        assert!(!effects.is_active(Effect::WidePaddle), "opposite effects end each other");
+        assert_eq!(effects.stacks(Effect::NarrowPaddle), 1);
+
+        effects.start(Effect::SlowBall, &config);
+        effects.start(Effect::SlowBall, &config);
+        let timer = &effects.0[&Effect::SlowBall].timer;
+        assert_eq!(timer.duration().as_secs_f32(), config.slow_ball.duration * 2.0);
+        assert_eq!(effects.stacks(Effect::SlowBall), 1);
+
+        effects.start(Effect::FastBall, &config);
+        assert!(!effects.is_active(Effect::SlowBall));
+        effects.start(Effect::FastBall, &config);
+        let timer = &effects.0[&Effect::FastBall].timer;
+        assert_eq!(timer.duration().as_secs_f32(), config.fast_ball.duration);
+    }
+
+    #[test]
+    fn test_effects_expire() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<GameConfig>()
+            .init_resource::<ActiveEffects>()
+            .add_event::<EffectEndedEvent>()
+            .add_systems(Update, tick_effects);
+
+        let config = app.world.resource::<GameConfig>().clone();
+        app.world.resource_mut::<ActiveEffects>().start(Effect::WidePaddle, &config.effects);
+        assert!(app.world.resource::<ActiveEffects>().paddle_width(&config) > config.paddle.size.x);
+
+        let step = Duration::from_secs_f32(config.effects.wide_paddle.duration / 2.0 + 0.1);
+        app.world.resource_mut::<Time>().advance_by(step);
+        app.update();
+        let remaining = app.world.resource::<ActiveEffects>().remaining(Effect::WidePaddle).unwrap();
+        assert_eq!(remaining, Duration::from_secs_f32(config.effects.wide_paddle.duration) - step);
+        assert!(app.world.resource::<Events<EffectEndedEvent>>().is_empty());
+
+        app.world.resource_mut::<Time>().advance_by(step);
+        app.update();
+        let effects = app.world.resource::<ActiveEffects>();
+        assert!(!effects.is_active(Effect::WidePaddle));
+        assert_eq!(effects.remaining(Effect::WidePaddle), None);
+        assert_eq!(effects.paddle_width(&config), config.paddle.size.x);
+
+        let mut reader = app.world.resource::<Events<EffectEndedEvent>>().get_reader();
+        let ended: Vec<Effect> = reader.read(app.world.resource()).map(|event| event.0).collect();
+        assert_eq!(ended, vec![Effect::WidePaddle]);
+    }
+
+    #[test]
+    fn test_fast_laser_stops_at_the_first_brick_it_passes() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<GameConfig>()
+            .init_resource::<Explosions>()
+            .insert_resource(BrickGrid::new(Vec2::splat(48.0)))
+            .add_event::<CollisionEvent>()
+            .add_systems(Update, check_laser_hits);
+        app.world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(0.1));
+
+        // both bricks are between where the laser was and where it is now
+        let size = app.world.resource::<GameConfig>().brick.collider_size();
+        let bricks = [30.0, 60.0].map(|y| {
+            let pos = Vec2::new(0.0, y);
+            let brick = app.world.spawn((Transform::from_translation(pos.extend(0.0)), Brick::new(1, 10, Color::WHITE))).id();
+            app.world.resource_mut::<BrickGrid>().insert(brick, pos, size);
+            brick
+        });
+        let laser = app.world.spawn((
+            Transform::from_xyz(0.0, 100.0, 0.0).with_scale(Vec3::new(2.0, 10.0, 0.0)),
+            Laser,
+            Velocity(Vec2::new(0.0, 1000.0)),
+        )).id();
+        app.update();
+
+        assert!(app.world.get_entity(laser).is_none());
+        assert!(app.world.get_entity(bricks[0]).is_none(), "the lower brick is hit first");
+        assert!(app.world.get_entity(bricks[1]).is_some());
+    }
+}
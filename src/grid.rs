@@ -0,0 +1,227 @@
+use bevy::{
+    ecs::{entity::Entity, system::Resource},
+    math::{IVec2, Vec2},
+    utils::HashMap,
+};
+
+/// Uniform grid broadphase for bricks.
+///
+/// Every brick is registered in each cell its collider overlaps, so the
+/// candidates for a swept ball are found by visiting only the cells its
+/// swept AABB touches.
+#[derive(Resource, Debug)]
+pub struct BrickGrid {
+    cell_size: Vec2,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    bricks: HashMap<Entity, (IVec2, IVec2)>,
+}
+
+impl BrickGrid {
+    pub fn new(cell_size: Vec2) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bricks: HashMap::new(),
+        }
+    }
+
+    pub fn cell_of(&self, pos: Vec2) -> IVec2 {
+        (pos / self.cell_size).floor().as_ivec2()
+    }
+
+    fn cell_range(&self, min: Vec2, max: Vec2) -> (IVec2, IVec2) {
+        (self.cell_of(min), self.cell_of(max))
+    }
+
+    /// Registers `entity` in every cell overlapped by the rectangle at `center`
+    /// with `size`. Inserting an entity again moves it to its new cells.
+    pub fn insert(&mut self, entity: Entity, center: Vec2, size: Vec2) {
+        let (min, max) = self.cell_range(center - size / 2.0, center + size / 2.0);
+        // moving bricks are inserted every tick, most of the time in the same cells
+        if self.bricks.get(&entity) == Some(&(min, max)) {
+            return
+        }
+        self.remove(entity);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+        self.bricks.insert(entity, (min, max));
+    }
+
+    /// Removes `entity` from all its cells, returns false if it was not registered.
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        let Some((min, max)) = self.bricks.remove(&entity) else {
+            return false
+        };
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let cell = IVec2::new(x, y);
+                if let Some(entities) = self.cells.get_mut(&cell) {
+                    entities.retain(|&e| e != entity);
+                    // drop cells left empty so they are not visited by later queries
+                    if entities.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.bricks.contains_key(&entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// Fills `out` with every brick registered in the cells touched by the
+    /// AABB `min`..`max`, each entity at most once.
+    pub fn query_aabb(&self, min: Vec2, max: Vec2, out: &mut Vec<Entity>) {
+        out.clear();
+
+        let (min, max) = self.cell_range(min, max);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(entities) = self.cells.get(&IVec2::new(x, y)) {
+                    out.extend_from_slice(entities);
+                }
+            }
+        }
+
+        if min != max {
+            out.sort_unstable();
+            out.dedup();
+        }
+    }
+}
+
+/// Spatial hash of short lived items, e.g. the balls of the current tick,
+/// cleared and filled again every time it is used.
+#[derive(Debug, Default)]
+pub struct SpatialHash {
+    cell_size: Vec2,
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialHash {
+    /// Empties the hash, keeping its allocation.
+    pub fn reset(&mut self, cell_size: Vec2) {
+        self.cell_size = cell_size;
+        self.cells.clear();
+    }
+
+    fn cell_of(&self, pos: Vec2) -> IVec2 {
+        (pos / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn insert(&mut self, index: usize, min: Vec2, max: Vec2) {
+        let (min, max) = (self.cell_of(min), self.cell_of(max));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Fills `out` with every item sharing a cell with the AABB `min`..`max`,
+    /// each item at most once.
+    pub fn query(&self, min: Vec2, max: Vec2, out: &mut Vec<usize>) {
+        out.clear();
+
+        let (min, max) = (self.cell_of(min), self.cell_of(max));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(items) = self.cells.get(&IVec2::new(x, y)) {
+                    out.extend_from_slice(items);
+                }
+            }
+        }
+
+        if min != max {
+            out.sort_unstable();
+            out.dedup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::entity::Entity, math::{IVec2, Vec2}};
+
+    use super::{BrickGrid, SpatialHash};
+
+    #[test]
+    fn test_insert_and_query() {
+        let mut grid = BrickGrid::new(Vec2::new(24.0, 24.0));
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+
+        // spans four cells around the origin
+        grid.insert(a, Vec2::ZERO, Vec2::new(12.0, 12.0));
+        grid.insert(b, Vec2::new(100.0, 100.0), Vec2::new(12.0, 12.0));
+
+        assert_eq!(grid.cell_of(Vec2::new(-0.5, 30.0)), IVec2::new(-1, 1));
+
+        let mut out = Vec::new();
+        grid.query_aabb(Vec2::new(-30.0, -30.0), Vec2::new(30.0, 30.0), &mut out);
+        assert_eq!(out, vec![a]);
+
+        grid.query_aabb(Vec2::new(90.0, 90.0), Vec2::new(91.0, 91.0), &mut out);
+        assert_eq!(out, vec![b]);
+
+        grid.query_aabb(Vec2::new(200.0, 200.0), Vec2::new(210.0, 210.0), &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut grid = BrickGrid::new(Vec2::new(24.0, 24.0));
+        let a = Entity::from_raw(1);
+
+        grid.insert(a, Vec2::ZERO, Vec2::new(12.0, 12.0));
+        assert!(grid.remove(a));
+        assert!(!grid.remove(a));
+        assert!(grid.bricks.is_empty());
+        assert!(grid.cells.is_empty());
+    }
+
+    #[test]
+    fn test_reinsert_moves_entity() {
+        let mut grid = BrickGrid::new(Vec2::new(24.0, 24.0));
+        let a = Entity::from_raw(1);
+
+        grid.insert(a, Vec2::new(12.0, 12.0), Vec2::new(12.0, 12.0));
+        grid.insert(a, Vec2::new(100.0, 12.0), Vec2::new(12.0, 12.0));
+        assert_eq!(grid.bricks.len(), 1);
+
+        let mut out = Vec::new();
+        grid.query_aabb(Vec2::new(0.0, 0.0), Vec2::new(20.0, 20.0), &mut out);
+        assert!(out.is_empty());
+        grid.query_aabb(Vec2::new(95.0, 0.0), Vec2::new(96.0, 20.0), &mut out);
+        assert_eq!(out, vec![a]);
+    }
+
+    #[test]
+    fn test_spatial_hash() {
+        let mut hash = SpatialHash::default();
+        hash.reset(Vec2::new(16.0, 16.0));
+
+        hash.insert(0, Vec2::new(-4.0, -4.0), Vec2::new(4.0, 4.0));
+        hash.insert(1, Vec2::new(10.0, 10.0), Vec2::new(14.0, 14.0));
+        hash.insert(2, Vec2::new(100.0, 100.0), Vec2::new(104.0, 104.0));
+
+        let mut out = Vec::new();
+        hash.query(Vec2::new(-2.0, -2.0), Vec2::new(12.0, 12.0), &mut out);
+        assert_eq!(out, vec![0, 1]);
+
+        hash.reset(Vec2::new(16.0, 16.0));
+        hash.query(Vec2::new(-2.0, -2.0), Vec2::new(12.0, 12.0), &mut out);
+        assert!(out.is_empty());
+    }
+}
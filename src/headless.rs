@@ -0,0 +1,129 @@
+use std::fmt;
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use rand::Rng;
+
+use crate::{
+    AppState, BreakoutPlugin, GameSet, Velocity,
+    ball::{Ball, Held, LaunchEvent},
+    config::{GameConfig, HEADLESS_LOAD_TIMEOUT},
+    high_score::HighScoresFile,
+    level::{Brick, CampaignProgress},
+    paddle::CursorWorldCoords,
+    score::{Lives, Score},
+};
+
+/// What runs the game without window, audio output or renderer, for CI and
+/// balancing runs: the paddle is driven by [`autopilot_paddle`], every update
+/// advances exactly one fixed timestep and high scores are not saved.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+                MinimalPlugins,
+                AssetPlugin::default(),
+                InputPlugin,
+            ))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_asset::<Image>()
+            .insert_resource(HighScoresFile(None))
+            .add_systems(FixedUpdate, autopilot_paddle.before(GameSet::Input).run_if(in_state(AppState::Level)));
+
+        let timestep = app.world.resource::<Time<Fixed>>().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    }
+}
+
+/// Outcome of [`run_headless`].
+#[derive(Debug, Clone, Copy)]
+pub struct HeadlessReport {
+    pub ticks: u32,
+    /// Index of the level being played in the campaign, or the number of
+    /// levels after a victory.
+    pub level: usize,
+    pub score: i32,
+    pub lives: u32,
+    pub bricks_left: usize,
+    pub balls: usize,
+}
+
+impl fmt::Display for HeadlessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ticks: {} level: {} score: {} lives: {} bricks left: {} balls: {}", self.ticks, self.level + 1, self.score, self.lives, self.bricks_left, self.balls)
+    }
+}
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    LevelNotLoaded(std::time::Duration),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LevelNotLoaded(timeout) => write!(f, "level did not load within {:?}, is the assets directory reachable?", timeout),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+/// Plays `ticks` fixed updates of the campaign under [`HeadlessPlugin`],
+/// starting once its first level is loaded.
+pub fn run_headless(ticks: u32) -> Result<HeadlessReport, HeadlessError> {
+    let mut app = App::new();
+    app.add_plugins((HeadlessPlugin, BreakoutPlugin));
+
+    let start = std::time::Instant::now();
+    while *app.world.resource::<State<AppState>>().get() != AppState::Level {
+        if start.elapsed() > HEADLESS_LOAD_TIMEOUT {
+            return Err(HeadlessError::LevelNotLoaded(HEADLESS_LOAD_TIMEOUT));
+        }
+        app.update();
+    }
+
+    for _ in 0..ticks {
+        app.update();
+    }
+
+    Ok(HeadlessReport {
+        ticks,
+        level: app.world.resource::<CampaignProgress>().level,
+        score: app.world.resource::<Score>().val,
+        lives: app.world.resource::<Lives>().0,
+        bricks_left: app.world.query::<&Brick>().iter(&app.world).count(),
+        balls: app.world.query_filtered::<(), With<Ball>>().iter(&app.world).count(),
+    })
+}
+
+/// Synthetic input for headless runs: launches served balls right away and
+/// keeps the cursor under the lowest ball falling towards the paddle, aiming at
+/// a random spot of the paddle for each ball it follows so that the game does
+/// not settle into a vertical loop.
+pub fn autopilot_paddle(
+    mut cursor_world_coords: ResMut<CursorWorldCoords>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    held_query: Query<(), With<Held>>,
+    mut launch_events: EventWriter<LaunchEvent>,
+    mut aim: Local<Option<f32>>,
+    config: Res<GameConfig>,
+) {
+    if !held_query.is_empty() {
+        launch_events.send(LaunchEvent);
+    }
+
+    let target = ball_query
+        .iter()
+        .filter(|(_, velocity)| velocity.y < 0.0)
+        .min_by(|a, b| a.0.translation.y.total_cmp(&b.0.translation.y));
+
+    let Some((ball_transform, _)) = target else {
+        *aim = None;
+        return
+    };
+
+    let aim = aim.get_or_insert_with(|| rand::thread_rng().gen_range(-0.8..0.8));
+    cursor_world_coords.x = ball_transform.translation.x - *aim * config.paddle.size.x / 2.0;
+}
@@ -0,0 +1,448 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    AppState,
+    config::{CAMPAIGN_PATH, GameConfig, HIGH_SCORES_PATH},
+    level::{Campaign, CampaignProgress},
+    score::{grant_time_bonus, Score},
+};
+
+/// Version of the high score file written by this build. Files of other
+/// versions still load: missing fields take their defaults and unknown ones
+/// are kept as they are.
+pub const HIGH_SCORES_VERSION: u32 = 1;
+
+/// Longest name that can be entered for a high score, in characters.
+const MAX_NAME_LENGTH: usize = 12;
+
+/// The two kinds of high score tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Board {
+    /// Points scored in a single level, by level path.
+    Level,
+    /// Points scored in a run of a campaign, by campaign path.
+    Campaign,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: i32,
+    /// When the score was set, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub time: u64,
+    /// Fields of later versions, written back untouched.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl HighScoreEntry {
+    pub fn new(name: String, score: i32) -> Self {
+        Self {
+            name,
+            score,
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()),
+            extra: default(),
+        }
+    }
+}
+
+/// The best scores of every level and campaign played, highest first, saved
+/// to [`HIGH_SCORES_PATH`] in the user data directory.
+///
+/// Loading skips the entries it cannot read, a file that cannot be read at
+/// all is kept aside as `.bak` and the game starts from empty tables.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HighScores {
+    pub version: u32,
+    /// The name last entered, offered again for the next high score.
+    pub last_name: String,
+    #[serde(deserialize_with = "readable_entries")]
+    pub levels: HashMap<String, Vec<HighScoreEntry>>,
+    #[serde(deserialize_with = "readable_entries")]
+    pub campaigns: HashMap<String, Vec<HighScoreEntry>>,
+    /// Fields of later versions, written back untouched.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        Self {
+            version: HIGH_SCORES_VERSION,
+            last_name: String::new(),
+            levels: HashMap::default(),
+            campaigns: HashMap::default(),
+            extra: default(),
+        }
+    }
+}
+
+fn readable_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Vec<HighScoreEntry>>, D::Error> {
+    let tables = HashMap::<String, Vec<serde_json::Value>>::deserialize(deserializer)?;
+    Ok(tables.into_iter()
+        .map(|(key, entries)| {
+            let mut entries: Vec<HighScoreEntry> = entries.into_iter()
+                .filter_map(|entry| serde_json::from_value(entry).ok())
+                .collect();
+            // the file may have been edited by hand
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+            (key, entries)
+        })
+        .collect())
+}
+
+impl HighScores {
+    pub fn table(&self, board: Board, key: &str) -> &[HighScoreEntry] {
+        let tables = match board {
+            Board::Level => &self.levels,
+            Board::Campaign => &self.campaigns,
+        };
+        tables.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// The rank, from 0, `score` would get in the table of `key` keeping
+    /// `top` entries, `None` when it does not make it.
+    pub fn rank(&self, board: Board, key: &str, score: i32, top: usize) -> Option<usize> {
+        let table = self.table(board, key);
+        // ties go to the older score
+        let rank = table.iter().position(|entry| entry.score < score).unwrap_or(table.len());
+        (score > 0 && rank < top).then_some(rank)
+    }
+
+    /// Adds `entry` to the table of `key` keeping `top` entries, returns its
+    /// rank when it made it.
+    pub fn insert(&mut self, board: Board, key: &str, entry: HighScoreEntry, top: usize) -> Option<usize> {
+        let rank = self.rank(board, key, entry.score, top)?;
+        let tables = match board {
+            Board::Level => &mut self.levels,
+            Board::Campaign => &mut self.campaigns,
+        };
+        let table = tables.entry(key.to_string()).or_default();
+        table.insert(rank, entry);
+        table.truncate(top);
+        Some(rank)
+    }
+
+    /// Reads the high scores at `path`, see [`HighScores`] for what happens
+    /// to files that cannot be read. A missing file is empty tables.
+    pub fn load(path: &Path) -> Self {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                warn!("cannot read high scores {}: {}", path.display(), err);
+                return Self::default()
+            }
+        };
+
+        match serde_json::from_str::<Self>(&json) {
+            Ok(high_scores) => {
+                if high_scores.version > HIGH_SCORES_VERSION {
+                    warn!("high scores {} were written by a newer version {}", path.display(), high_scores.version);
+                }
+                high_scores
+            }
+            Err(err) => {
+                let backup = path.with_extension("json.bak");
+                warn!("corrupt high scores {}, moved to {}: {}", path.display(), backup.display(), err);
+                if let Err(err) = fs::rename(path, &backup) {
+                    warn!("cannot move {}: {}", path.display(), err);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the high scores to `path` through a temporary file, so that a
+    /// crash while saving leaves the previous file intact.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut high_scores = self.clone();
+        high_scores.version = self.version.max(HIGH_SCORES_VERSION);
+        let json = serde_json::to_string_pretty(&high_scores).map_err(io::Error::other)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, json)?;
+        fs::rename(&temporary, path)
+    }
+}
+
+/// Where the [`HighScores`] are saved, `None` to neither save them nor ask for
+/// a name, as headless runs do. Defaults to [`HIGH_SCORES_PATH`] in the user
+/// data directory, when the platform has one.
+#[derive(Resource, Debug, Clone)]
+pub struct HighScoresFile(pub Option<PathBuf>);
+
+impl Default for HighScoresFile {
+    fn default() -> Self {
+        Self(dirs::data_dir().map(|dir| dir.join(HIGH_SCORES_PATH)))
+    }
+}
+
+/// The scores of the current run that made a high score table, waiting for
+/// the player's name once the run ends.
+#[derive(Resource, Default, Debug)]
+pub struct PendingHighScores {
+    /// The score when the current level started.
+    pub level_start: i32,
+    pub scores: Vec<(Board, String, i32)>,
+}
+
+/// The name being typed for the [`PendingHighScores`], present from the end
+/// of a run that made a table until the name is confirmed with Enter.
+#[derive(Resource, Debug, Default)]
+pub struct NameEntry(pub String);
+
+/// The high score tables shown once a run ends.
+#[derive(Component)]
+pub struct HighScoreText;
+
+/// Keeps the best scores of every level cleared and of every run of the
+/// campaign. Once a run ends, by a victory or a game over, the player types a
+/// name for the scores that made a table, then they are saved.
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighScoresFile>()
+            .init_resource::<Score>()
+            .init_resource::<PendingHighScores>()
+            .add_event::<ReceivedCharacter>()
+            .add_systems(Startup, load_high_scores)
+            .add_systems(OnEnter(AppState::Level), start_level_score)
+            .add_systems(OnEnter(AppState::LevelCleared), record_level_score.after(grant_time_bonus))
+            .add_systems(OnEnter(AppState::GameOver), (record_campaign_score, show_high_scores).chain())
+            .add_systems(OnEnter(AppState::Victory), (record_campaign_score, show_high_scores).chain())
+            .add_systems(OnExit(AppState::GameOver), hide_high_scores)
+            .add_systems(Update, (
+                enter_name.run_if(resource_exists::<NameEntry>()),
+                update_high_score_text.run_if(
+                    resource_changed::<HighScores>().or_else(resource_exists_and_changed::<NameEntry>()),
+                ),
+            ).chain());
+    }
+}
+
+fn load_high_scores(mut commands: Commands, file: Res<HighScoresFile>) {
+    let high_scores = file.0.as_deref().map_or_else(HighScores::default, HighScores::load);
+    commands.insert_resource(high_scores);
+}
+
+fn start_level_score(mut pending: ResMut<PendingHighScores>, score: Res<Score>) {
+    pending.level_start = score.val;
+}
+
+/// The path of the campaign being played and of its current level.
+fn campaign_keys(
+    progress: &CampaignProgress,
+    campaigns: &Assets<Campaign>,
+    asset_server: &AssetServer,
+) -> (String, Option<String>) {
+    let campaign = asset_server.get_path(progress.campaign.id())
+        .map_or_else(|| CAMPAIGN_PATH.to_string(), |path| path.to_string());
+    let level = campaigns.get(&progress.campaign).and_then(|campaign| campaign.levels.get(progress.level).cloned());
+    (campaign, level)
+}
+
+fn record_level_score(
+    mut pending: ResMut<PendingHighScores>,
+    high_scores: Res<HighScores>,
+    score: Res<Score>,
+    progress: Res<CampaignProgress>,
+    campaigns: Res<Assets<Campaign>>,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
+    let (_, Some(level)) = campaign_keys(&progress, &campaigns, &asset_server) else {
+        return
+    };
+    let level_score = score.val - pending.level_start;
+    if high_scores.rank(Board::Level, &level, level_score, config.score.high_scores).is_some() {
+        pending.scores.push((Board::Level, level, level_score));
+    }
+}
+
+fn record_campaign_score(
+    mut commands: Commands,
+    mut pending: ResMut<PendingHighScores>,
+    high_scores: Res<HighScores>,
+    score: Res<Score>,
+    progress: Res<CampaignProgress>,
+    campaigns: Res<Assets<Campaign>>,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    file: Res<HighScoresFile>,
+) {
+    if file.0.is_none() {
+        // nowhere to keep them, so no name to ask for
+        pending.scores.clear();
+        return
+    }
+
+    let (campaign, _) = campaign_keys(&progress, &campaigns, &asset_server);
+    if high_scores.rank(Board::Campaign, &campaign, score.val, config.score.high_scores).is_some() {
+        pending.scores.push((Board::Campaign, campaign, score.val));
+    }
+    if !pending.scores.is_empty() {
+        commands.insert_resource(NameEntry(high_scores.last_name.clone()));
+    }
+}
+
+/// Types the name of the pending high scores, Enter saves them under it.
+fn enter_name(
+    mut commands: Commands,
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut name: ResMut<NameEntry>,
+    mut pending: ResMut<PendingHighScores>,
+    mut high_scores: ResMut<HighScores>,
+    file: Res<HighScoresFile>,
+    config: Res<GameConfig>,
+) {
+    for event in characters.read() {
+        if !event.char.is_control() && name.0.chars().count() < MAX_NAME_LENGTH {
+            name.0.push(event.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        name.0.pop();
+    }
+
+    let entered = name.0.trim();
+    if !keyboard_input.just_pressed(KeyCode::Return) || entered.is_empty() {
+        return
+    }
+
+    for (board, key, score) in pending.scores.drain(..) {
+        high_scores.insert(board, &key, HighScoreEntry::new(entered.to_string(), score), config.score.high_scores);
+    }
+    high_scores.last_name = entered.to_string();
+    commands.remove_resource::<NameEntry>();
+
+    if let Some(path) = &file.0 {
+        if let Err(err) = high_scores.save(path) {
+            warn!("cannot save high scores {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn show_high_scores(mut commands: Commands, text_query: Query<(), With<HighScoreText>>) {
+    if !text_query.is_empty() {
+        return
+    }
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 24.0,
+            color: Color::WHITE,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            left: Val::Percent(25.0),
+            ..default()
+        }),
+        HighScoreText,
+    ));
+}
+
+fn hide_high_scores(
+    mut commands: Commands,
+    text_query: Query<Entity, With<HighScoreText>>,
+    mut pending: ResMut<PendingHighScores>,
+) {
+    for entity in &text_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    // scores whose name was never entered are lost with the run
+    pending.scores.clear();
+    commands.remove_resource::<NameEntry>();
+}
+
+fn update_high_score_text(
+    mut text_query: Query<&mut Text, With<HighScoreText>>,
+    high_scores: Res<HighScores>,
+    name: Option<Res<NameEntry>>,
+    progress: Res<CampaignProgress>,
+    campaigns: Res<Assets<Campaign>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return
+    };
+
+    let (campaign, _) = campaign_keys(&progress, &campaigns, &asset_server);
+    let mut value = String::from("HIGH SCORES\n\n");
+    for (rank, entry) in high_scores.table(Board::Campaign, &campaign).iter().enumerate() {
+        value += &format!("{:>2}. {:<12} {:>8}\n", rank + 1, entry.name, entry.score);
+    }
+    match name {
+        Some(name) => value += &format!("\nNEW HIGH SCORE! Your name: {}_\n", name.0),
+        None => value += "\nPress to play again\n",
+    }
+    text.sections[0].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, HighScoreEntry, HighScores, HIGH_SCORES_VERSION};
+
+    #[test]
+    fn test_ranking_keeps_the_top() {
+        let mut high_scores = HighScores::default();
+        for (name, score) in [("a", 10), ("b", 30), ("c", 20), ("d", 20)] {
+            high_scores.insert(Board::Level, "levels/level_1.json", HighScoreEntry::new(name.to_string(), score), 3);
+        }
+
+        let names: Vec<&str> = high_scores.table(Board::Level, "levels/level_1.json").iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["b", "c", "d"], "ties rank after the older score");
+        assert_eq!(high_scores.rank(Board::Level, "levels/level_1.json", 20, 3), None);
+        assert_eq!(high_scores.rank(Board::Level, "levels/level_1.json", 21, 3), Some(1));
+        assert_eq!(high_scores.rank(Board::Campaign, "levels/level_1.json", 0, 3), None, "nothing scored is no high score");
+        assert!(high_scores.table(Board::Campaign, "levels/level_1.json").is_empty());
+    }
+
+    #[test]
+    fn test_loading_tolerates_old_new_and_broken_saves() {
+        // written before the schema had a version, with one broken entry
+        let old: HighScores = serde_json::from_str(r#"{
+            "campaigns": { "levels/main.campaign.json": [
+                { "name": "a", "score": 5 },
+                { "name": "b" },
+                { "name": "c", "score": 9 }
+            ] }
+        }"#).unwrap();
+        let table = old.table(Board::Campaign, "levels/main.campaign.json");
+        assert_eq!(table.iter().map(|entry| entry.score).collect::<Vec<_>>(), [9, 5]);
+        assert_eq!(old.version, HIGH_SCORES_VERSION);
+
+        // written by a later version with fields this one does not know
+        let json = r#"{ "version": 7, "medals": 3, "levels": { "x": [{ "name": "a", "score": 1, "combo": 4 }] } }"#;
+        let new: HighScores = serde_json::from_str(json).unwrap();
+        let saved: serde_json::Value = serde_json::to_value(&new).unwrap();
+        assert_eq!(saved["version"], 7);
+        assert_eq!(saved["medals"], 3);
+        assert_eq!(saved["levels"]["x"][0]["combo"], 4);
+
+        let path = std::env::temp_dir().join(format!("breakout-high-scores-{}", std::process::id())).join("high_scores.json");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{ \"levels\": [1, 2").unwrap();
+        assert_eq!(HighScores::load(&path), HighScores::default());
+        assert!(path.with_extension("json.bak").exists(), "the broken file is kept aside");
+
+        new.save(&path).unwrap();
+        assert_eq!(HighScores::load(&path), new);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
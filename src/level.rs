@@ -0,0 +1,670 @@
+use std::fmt;
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, ReadAssetBytesError, io::Reader},
+    prelude::*,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    AppState, GameSet,
+    ball::BallKind,
+    brick::{
+        BrickCollider, BrickMotion, BrickTypeName, BrickTypeRegistry, DropTable, ExplosionEvent, Explosions, Explosive,
+        Motion,
+        clear_explosions, detonate_explosions, move_bricks,
+    },
+    config::{BRICK_TYPES_PATH, CAMPAIGN_PATH, GameConfig},
+    grid::BrickGrid,
+    json_plugin::JsonAssetPlugin,
+};
+
+/// A destructible brick, broken once its `hp` is down to zero, which scores
+/// `score` points. `color` is the color of the brick at full health.
+#[derive(Component)]
+pub struct Brick {
+    pub destroy: bool,
+    pub hp: u32,
+    pub score: i32,
+    pub color: Color,
+}
+
+impl Brick {
+    pub fn new(hp: u32, score: i32, color: Color) -> Self {
+        Self {
+            destroy: false,
+            hp: hp.max(1),
+            score,
+            color,
+        }
+    }
+
+    /// Takes `damage` hit points off the brick, returns true once it is broken.
+    pub fn damage(&mut self, damage: u32) -> bool {
+        self.hp = self.hp.saturating_sub(damage);
+        self.destroy = self.hp == 0;
+        self.destroy
+    }
+}
+
+#[derive(Component)]
+pub struct WallBlock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColliderType {
+    Wall,
+    Brick,
+    Paddle,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Collider(pub ColliderType);
+
+/// A brick of a level, of the [`BrickType`](crate::brick::BrickType) named
+/// `brick_type`. `color`, `hp` and `score` override the type's when set, the
+/// brick moves along `motion` when it has one.
+#[derive(Default, Serialize, Deserialize,Debug)]
+pub struct BrickData {
+   pub brick_type: String,
+   #[serde(default)]
+   pub color: Option<Color>,
+   pub pos: Vec2,
+   #[serde(default)]
+   pub hp: Option<u32>,
+   #[serde(default)]
+   pub score: Option<i32>,
+   #[serde(default)]
+   pub motion: Option<BrickMotion>,
+}
+
+#[derive(Serialize, Deserialize, Asset, TypePath,Debug)]
+pub struct Level {
+    pub bricks: Vec<BrickData>,
+    #[serde(default, flatten)]
+    pub rules: LevelRules,
+    /// The brick types, filled in by the loader.
+    #[serde(skip)]
+    pub brick_types: BrickTypeRegistry,
+}
+
+impl Level {
+    /// Checks that every brick names a type of `registry`, which the level
+    /// then uses.
+    pub fn resolve(&mut self, registry: BrickTypeRegistry) -> Result<(), LevelLoadError> {
+        let unknown: Vec<(usize, String)> = self.bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, brick)| registry.get(&brick.brick_type).is_none())
+            .map(|(index, brick)| (index, brick.brick_type.clone()))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(LevelLoadError::UnknownBrickTypes(unknown));
+        }
+
+        self.brick_types = registry;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelLoadError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    BrickTypes(ReadAssetBytesError),
+    /// Index and type name of each brick whose type is not registered.
+    UnknownBrickTypes(Vec<(usize, String)>),
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the level: {}", err),
+            Self::Json(err) => write!(f, "could not parse the level: {}", err),
+            Self::BrickTypes(err) => write!(f, "could not read the brick types: {}", err),
+            Self::UnknownBrickTypes(bricks) => {
+                write!(f, "unknown brick types:")?;
+                for (index, name) in bricks {
+                    write!(f, " \"{}\" at brick {},", name, index)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelLoadError {}
+
+/// Loads a [`Level`] along with the brick types it uses.
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+
+    type Settings = ();
+
+    type Error = LevelLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await.map_err(LevelLoadError::Io)?;
+            let mut level = serde_json::from_slice::<Level>(&bytes).map_err(LevelLoadError::Json)?;
+
+            let registry = load_context.read_asset_bytes(BRICK_TYPES_PATH).await.map_err(LevelLoadError::BrickTypes)?;
+            level.resolve(serde_json::from_slice(&registry).map_err(LevelLoadError::Json)?)?;
+            Ok(level)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// Level wide rules, in effect while the level is played.
+///
+/// `palette` holds the colors of damaged bricks by remaining hit points, the
+/// first entry for 1 HP left, the last one for any higher count. A brick keeps
+/// its own color until it is hit, and always when the palette is empty.
+/// `ball_damage` is how many hit points each kind of ball takes off a brick,
+/// 1 for the kinds it does not list. `drop_table` replaces the default drop
+/// table for the bricks of the level, see [`DropTables`](crate::drop_table::DropTables).
+/// `par_time` replaces [`ScoreConfig::par_time`](crate::config::ScoreConfig::par_time)
+/// for the level.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LevelRules {
+    #[serde(default)]
+    pub palette: Vec<Color>,
+    #[serde(default)]
+    pub ball_damage: HashMap<BallKind, u32>,
+    #[serde(default)]
+    pub drop_table: Option<String>,
+    #[serde(default)]
+    pub par_time: Option<f32>,
+}
+
+impl LevelRules {
+    pub fn damage(&self, kind: BallKind) -> u32 {
+        self.ball_damage.get(&kind).copied().unwrap_or(1)
+    }
+
+    pub fn color(&self, brick: &Brick, max_hp: u32) -> Color {
+        if brick.hp >= max_hp || self.palette.is_empty() {
+            return brick.color
+        }
+        self.palette[(brick.hp.max(1) as usize - 1).min(self.palette.len() - 1)]
+    }
+}
+
+/// Hit points of a brick when spawned, for [`LevelRules::color`].
+#[derive(Component)]
+pub struct MaxHp(pub u32);
+
+/// The current level, kept loaded so that it can be spawned again.
+#[derive(Resource)]
+pub struct LevelHandler(pub Handle<Level>);
+
+/// Ordered list of the levels to play, as paths relative to the assets directory.
+#[derive(Serialize, Deserialize, Asset, TypePath, Debug)]
+pub struct Campaign {
+    pub levels: Vec<String>,
+}
+
+/// The campaign being played and the index of the current level in it.
+#[derive(Resource)]
+pub struct CampaignProgress {
+    pub campaign: Handle<Campaign>,
+    pub level: usize,
+}
+
+#[derive(Resource)]
+struct LevelTransitionTimer(Timer);
+
+/// Plays the levels of the campaign in order: loads the current level, spawns
+/// its bricks and walls, detects when it is cleared and moves on to the next
+/// one, or to [`AppState::Victory`] after the last one.
+///
+/// Also keeps the [`BrickGrid`] the balls collide against in step with the
+/// bricks and with the [`GameConfig`].
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(JsonAssetPlugin::<Campaign>::new(&["campaign.json"]))
+            .init_asset::<Level>()
+            .register_asset_loader(LevelLoader)
+            .insert_resource(BrickGrid::new(GameConfig::default().brick.grid_cell_size()))
+            .init_resource::<LevelRules>()
+            .init_resource::<Explosions>()
+            .add_event::<ExplosionEvent>()
+            .add_systems(Startup, load_campaign)
+            .add_systems(Update, (
+                (load_level, spawn_level).chain().run_if(in_state(AppState::Loading)),
+                (tint_damaged_bricks, check_level_cleared).run_if(in_state(AppState::Level)),
+                advance_level.run_if(in_state(AppState::LevelCleared)),
+            ))
+            .add_systems(FixedUpdate, (
+                move_bricks.in_set(GameSet::Movement),
+                detonate_explosions.in_set(GameSet::BallCollision),
+            ))
+            .add_systems(OnExit(AppState::Level), clear_explosions)
+            .add_systems(OnEnter(AppState::LevelCleared), (despawn_bricks, start_level_transition))
+            // the current level is kept loaded and spawned again
+            .add_systems(OnExit(AppState::GameOver), despawn_bricks)
+            .add_systems(PostUpdate, (
+                resize_bricks.run_if(resource_changed::<GameConfig>()),
+                sync_brick_grid,
+                check_brick_grid,
+            ).chain());
+    }
+}
+
+fn load_campaign(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(CampaignProgress {
+        campaign: asset_server.load(CAMPAIGN_PATH),
+        level: 0,
+    });
+}
+
+fn load_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    campaigns: Res<Assets<Campaign>>,
+    progress: Res<CampaignProgress>,
+    level_handler: Option<Res<LevelHandler>>,
+)  {
+    if level_handler.is_some() {
+        return
+    }
+    let Some(campaign) = campaigns.get(&progress.campaign) else {
+        return
+    };
+
+    if let Some(path) = campaign.levels.get(progress.level) {
+        commands.insert_resource(LevelHandler(asset_server.load(path)));
+    }
+}
+
+fn spawn_level(
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    level_handle: Option<Res<LevelHandler>>,
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<NextState<AppState>>,
+    config: Res<GameConfig>,
+){
+    let Some(level_handle) = level_handle else {
+        return
+    };
+
+    let brick_size = config.brick.size.extend(0.0);
+    if let Some(level) = levels.get(&level_handle.0) {
+        commands.insert_resource(level.rules.clone());
+        for (index, level_brick) in level.bricks.iter().enumerate() {
+            // the loader made sure every type exists
+            let Some(brick_type) = level.brick_types.get(&level_brick.brick_type) else {
+                warn!("unknown brick type \"{}\" at brick {}", level_brick.brick_type, index);
+                continue;
+            };
+
+            let color = level_brick.color.unwrap_or(brick_type.color);
+            let mut entity = commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        // the texture is stretched over the brick, whose size is its scale
+                        custom_size: brick_type.sprite.as_ref().map(|_| Vec2::ONE),
+                        ..default()
+                    },
+                    texture: brick_type.sprite.as_ref().map_or_else(default, |path| asset_server.load(path)),
+                    transform: Transform::from_translation(level_brick.pos.extend(0.0)).with_scale(brick_size),
+                    ..default()
+                },
+                BrickTypeName(level_brick.brick_type.clone()),
+            ));
+
+            match brick_type.collider {
+                BrickCollider::Breakable => {
+                    let hp = level_brick.hp.unwrap_or(brick_type.hp).max(1);
+                    entity.insert((
+                        Brick::new(hp, level_brick.score.unwrap_or(brick_type.score), color),
+                        MaxHp(hp),
+                        Collider(ColliderType::Brick),
+                    ));
+                }
+                BrickCollider::Wall => {
+                    entity.insert((
+                        WallBlock,
+                        Collider(ColliderType::Wall),
+                    ));
+                }
+            }
+
+            if let Some(drop_table) = &brick_type.drop_table {
+                entity.insert(DropTable(drop_table.clone()));
+            }
+            if let Some(explosion) = brick_type.explosion {
+                entity.insert(Explosive(explosion));
+            }
+            if let Some(motion) = &level_brick.motion {
+                entity.insert(Motion::new(motion.clone(), level_brick.pos));
+            }
+        }
+
+        state.set(AppState::Level);
+    }
+}
+
+fn tint_damaged_bricks(
+    mut brick_query: Query<(&Brick, &MaxHp, &mut Sprite), Changed<Brick>>,
+    rules: Res<LevelRules>,
+) {
+    for (brick, max_hp, mut sprite) in &mut brick_query {
+        sprite.color = rules.color(brick, max_hp.0);
+    }
+}
+
+/// A level is cleared once no destructible brick is left, walls do not count.
+fn check_level_cleared(
+    brick_query: Query<&Brick>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if brick_query.iter().all(|brick| brick.destroy) {
+        state.set(AppState::LevelCleared);
+    }
+}
+
+fn despawn_bricks(
+    mut commands: Commands,
+    brick_query: Query<Entity, Or<(With<Brick>, With<WallBlock>)>>,
+) {
+    for entity in &brick_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn start_level_transition(mut commands: Commands, config: Res<GameConfig>) {
+    commands.insert_resource(LevelTransitionTimer(Timer::from_seconds(config.level_transition, TimerMode::Once)));
+}
+
+fn advance_level(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: ResMut<LevelTransitionTimer>,
+    mut progress: ResMut<CampaignProgress>,
+    campaigns: Res<Assets<Campaign>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if !timer.0.tick(time.delta()).finished() {
+        return
+    }
+
+    progress.level += 1;
+    commands.remove_resource::<LevelHandler>();
+    commands.remove_resource::<LevelTransitionTimer>();
+
+    let level_count = campaigns.get(&progress.campaign).map_or(0, |campaign| campaign.levels.len());
+    if progress.level < level_count {
+        state.set(AppState::Loading);
+    } else {
+        state.set(AppState::Victory);
+    }
+}
+
+/// Keeps the [`BrickGrid`] in step with the brick entities: bricks are added as
+/// soon as they are spawned, whether by `spawn_level` or at runtime, and removed
+/// once they are despawned, however that happened.
+///
+/// Runs every frame so that no removal is missed, even when `FixedUpdate` did
+/// not tick during the frame.
+fn sync_brick_grid(
+    mut grid: ResMut<BrickGrid>,
+    mut removed: RemovedComponents<Collider>,
+    brick_query: Query<(Entity, &Transform, &Collider), Added<Collider>>,
+    config: Res<GameConfig>,
+) {
+    for entity in removed.read() {
+        grid.remove(entity);
+    }
+
+    for (entity, transform, collider) in &brick_query {
+        if matches!(collider.0, ColliderType::Brick | ColliderType::Wall) {
+            grid.insert(entity, transform.translation.truncate(), config.brick.collider_size());
+        }
+    }
+}
+
+/// Applies a new brick size to the bricks in play and rebuilds the
+/// [`BrickGrid`] around them.
+fn resize_bricks(
+    mut grid: ResMut<BrickGrid>,
+    mut brick_query: Query<(Entity, &mut Transform, &Collider)>,
+    config: Res<GameConfig>,
+) {
+    *grid = BrickGrid::new(config.brick.grid_cell_size());
+    for (entity, mut transform, collider) in &mut brick_query {
+        if matches!(collider.0, ColliderType::Brick | ColliderType::Wall) {
+            transform.scale = config.brick.size.extend(0.0);
+            grid.insert(entity, transform.translation.truncate(), config.brick.collider_size());
+        }
+    }
+}
+
+/// Debug check that the [`BrickGrid`] holds exactly the live bricks.
+fn check_brick_grid(
+    grid: Res<BrickGrid>,
+    brick_query: Query<(Entity, &Collider)>,
+) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let mut live = 0;
+    for (entity, collider) in &brick_query {
+        if matches!(collider.0, ColliderType::Brick | ColliderType::Wall) {
+            live += 1;
+            debug_assert!(grid.contains(entity), "brick {:?} is missing from the grid", entity);
+        }
+    }
+    debug_assert_eq!(live, grid.len(), "grid holds bricks that are no longer alive");
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{
+        Brick, BrickData, BrickTypeRegistry, LevelLoadError, Campaign, CampaignProgress, Collider, ColliderType, Level, LevelHandler, LevelPlugin,
+        WallBlock, sync_brick_grid, check_brick_grid,
+    };
+    use crate::{AppState, ball::BallKind, config::GameConfig, grid::BrickGrid};
+
+    fn spawn_brick(app: &mut App, pos: Vec2) -> Entity {
+        app.world.spawn((
+            Transform::from_translation(pos.extend(0.0)),
+            Brick::new(1, 1, Color::WHITE),
+            Collider(ColliderType::Brick),
+        )).id()
+    }
+
+    #[test]
+    fn test_brick_grid_follows_despawned_bricks() {
+        let mut app = App::new();
+        app.init_resource::<GameConfig>()
+            .insert_resource(BrickGrid::new(GameConfig::default().brick.grid_cell_size()))
+            .add_systems(PostUpdate, (sync_brick_grid, check_brick_grid).chain());
+
+        let bricks: Vec<Entity> = (0..20)
+            .map(|i| spawn_brick(&mut app, Vec2::new(i as f32 * 12.0, 100.0)))
+            .collect();
+        app.update();
+        assert_eq!(app.world.resource::<BrickGrid>().len(), bricks.len());
+
+        for &brick in bricks.iter().step_by(2) {
+            app.world.despawn(brick);
+        }
+        spawn_brick(&mut app, Vec2::new(-50.0, -50.0));
+        app.update();
+
+        let grid = app.world.resource::<BrickGrid>();
+        assert_eq!(grid.len(), bricks.len() / 2 + 1);
+        for (i, &brick) in bricks.iter().enumerate() {
+            assert_eq!(grid.contains(brick), i % 2 == 1);
+        }
+
+        // removals are still seen after frames without any change
+        app.update();
+        app.update();
+        for &brick in bricks.iter().skip(1).step_by(2) {
+            app.world.despawn(brick);
+        }
+        app.update();
+        assert_eq!(app.world.resource::<BrickGrid>().len(), 1);
+    }
+
+    fn registry() -> BrickTypeRegistry {
+        serde_json::from_str(r#"{ "normal": {}, "wall": { "collider": "Wall" } }"#).unwrap()
+    }
+
+    fn add_level(app: &mut App, bricks: u8) {
+        let mut level = Level {
+            bricks: (0..bricks + 1)
+                .map(|i| BrickData {
+                    // the last one is a wall
+                    brick_type: if i == bricks { "wall" } else { "normal" }.into(),
+                    pos: Vec2::new(i as f32 * 12.0, 100.0),
+                    ..default()
+                })
+                .collect(),
+            rules: default(),
+            brick_types: default(),
+        };
+        level.resolve(registry()).unwrap();
+        let handle = app.world.resource_mut::<Assets<Level>>().add(level);
+        app.world.insert_resource(LevelHandler(handle));
+    }
+
+    fn clear_bricks(app: &mut App) {
+        let bricks: Vec<Entity> = app.world.query_filtered::<Entity, With<Brick>>().iter(&app.world).collect();
+        for brick in bricks {
+            app.world.despawn(brick);
+        }
+    }
+
+    fn state(app: &App) -> AppState {
+        *app.world.resource::<State<AppState>>().get()
+    }
+
+    #[test]
+    fn test_campaign_progression() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), LevelPlugin))
+            .add_state::<AppState>()
+            .insert_resource(GameConfig {
+                level_transition: 0.0,
+                ..default()
+            });
+        app.update();
+
+        let campaign = app.world.resource_mut::<Assets<Campaign>>().add(Campaign {
+            levels: vec!["first.json".into(), "second.json".into()],
+        });
+        app.world.insert_resource(CampaignProgress { campaign, level: 0 });
+        add_level(&mut app, 3);
+
+        app.update();
+        app.update();
+        assert_eq!(state(&app), AppState::Level);
+        assert_eq!(app.world.query::<&Brick>().iter(&app.world).count(), 3);
+
+        // walls do not need to be broken
+        clear_bricks(&mut app);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), AppState::LevelCleared);
+        assert_eq!(app.world.query::<&WallBlock>().iter(&app.world).count(), 0);
+
+        app.update();
+        app.update();
+        assert_eq!(state(&app), AppState::Loading);
+        assert_eq!(app.world.resource::<CampaignProgress>().level, 1);
+
+        add_level(&mut app, 2);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), AppState::Level);
+
+        clear_bricks(&mut app);
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(state(&app), AppState::Victory);
+    }
+
+    #[test]
+    fn test_level_hit_points_and_rules() {
+        let level: Level = serde_json::from_str(r#"{
+            "palette": [{ "Rgba": { "red": 1.0, "green": 0.0, "blue": 0.0, "alpha": 1.0 } }],
+            "ball_damage": { "Fireball": 3 },
+            "bricks": [
+                { "brick_type": "normal", "color": { "Rgba": { "red": 0.0, "green": 0.0, "blue": 1.0, "alpha": 1.0 } }, "pos": [0.0, 0.0], "hp": 3, "score": 5 },
+                { "brick_type": "normal", "pos": [12.0, 0.0] }
+            ]
+        }"#).unwrap();
+
+        assert_eq!((level.bricks[0].hp, level.bricks[0].score), (Some(3), Some(5)));
+        assert_eq!((level.bricks[1].hp, level.bricks[1].score, level.bricks[1].color), (None, None, None));
+        assert_eq!(level.rules.damage(BallKind::Normal), 1);
+        assert_eq!(level.rules.damage(BallKind::Fireball), 3);
+
+        let color = level.bricks[0].color.unwrap();
+        let mut brick = Brick::new(3, 5, color);
+        assert_eq!(level.rules.color(&brick, 3), color);
+        assert!(!brick.damage(1));
+        assert_eq!(level.rules.color(&brick, 3), Color::rgba(1.0, 0.0, 0.0, 1.0));
+        assert!(brick.damage(level.rules.damage(BallKind::Fireball)));
+        assert_eq!(brick.hp, 0);
+    }
+
+    #[test]
+    fn test_unknown_brick_types_fail_the_level() {
+        let mut level: Level = serde_json::from_str(r#"{ "bricks": [
+            { "brick_type": "normal", "pos": [0.0, 0.0] },
+            { "brick_type": "rubber", "pos": [12.0, 0.0] },
+            { "brick_type": "wall", "pos": [24.0, 0.0] },
+            { "brick_type": "gold", "pos": [36.0, 0.0] }
+        ] }"#).unwrap();
+
+        let Err(LevelLoadError::UnknownBrickTypes(unknown)) = level.resolve(registry()) else {
+            panic!("unknown brick types were accepted");
+        };
+        assert_eq!(unknown, vec![(1, "rubber".to_string()), (3, "gold".to_string())]);
+        assert_eq!(
+            LevelLoadError::UnknownBrickTypes(unknown).to_string(),
+            "unknown brick types: \"rubber\" at brick 1, \"gold\" at brick 3,",
+        );
+    }
+
+    #[test]
+    fn test_shipped_levels_use_registered_brick_types() {
+        let registry: BrickTypeRegistry = serde_json::from_str(include_str!("../assets/levels/bricks.bricktypes.json")).unwrap();
+        let campaign: Campaign = serde_json::from_str(include_str!("../assets/levels/main.campaign.json")).unwrap();
+
+        for path in &campaign.levels {
+            let bytes = std::fs::read(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(path)).unwrap();
+            let mut level: Level = serde_json::from_slice(&bytes).unwrap();
+            if let Err(err) = level.resolve(registry.clone()) {
+                panic!("{}: {}", path, err);
+            }
+        }
+    }
+}
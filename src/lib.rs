@@ -0,0 +1,125 @@
+//! Breakout as a set of bevy plugins.
+//!
+//! [`BreakoutPlugin`] adds the whole game, its members can be replaced one by
+//! one through [`PluginGroup::build`]. [`RewardPlugin`], [`EffectPlugin`],
+//! [`ScorePlugin`], [`HighScorePlugin`] and [`PausePlugin`] can also be
+//! disabled, the others are what they build on. None of them needs a window,
+//! audio output or renderer, see [`headless`].
+
+// bevy systems routinely take many, deeply nested query parameters
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+mod collide;
+mod grid;
+
+pub mod ball;
+pub mod brick;
+pub mod config;
+pub mod drop_table;
+pub mod effect;
+pub mod headless;
+pub mod high_score;
+pub mod json_plugin;
+pub mod level;
+pub mod paddle;
+pub mod pause;
+pub mod reward;
+pub mod score;
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub use ball::BallPlugin;
+pub use effect::EffectPlugin;
+pub use high_score::HighScorePlugin;
+pub use level::LevelPlugin;
+pub use paddle::PaddlePlugin;
+pub use pause::PausePlugin;
+pub use reward::RewardPlugin;
+pub use score::ScorePlugin;
+
+#[derive(Debug,Clone, Copy,Default,Eq,PartialEq,Hash,States)]
+pub enum AppState {
+    /// Waiting for the campaign and the current level to load.
+    #[default]
+    Loading,
+    Level,
+    /// The last brick was broken, the level is cleared away before the next one loads.
+    LevelCleared,
+    /// Every level of the campaign was cleared.
+    Victory,
+    /// The last life was lost. Going back to [`AppState::Loading`] restarts the
+    /// current level from scratch.
+    GameOver,
+}
+
+/// Whether the game is paused, independently of the [`AppState`] so that
+/// pausing does not count as leaving the level.
+#[derive(Debug,Clone, Copy,Default,Eq,PartialEq,Hash,States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Order of the gameplay systems inside `FixedUpdate`, which only run while a
+/// level is being played and the game is not paused.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    /// Moves the paddle from the player's input.
+    Input,
+    /// Moves everything with a [`Velocity`] but the balls.
+    Movement,
+    /// Balls and falling rewards against the paddle.
+    PaddleCollision,
+    /// Balls against each other, the bricks and the arena, moves the balls.
+    BallCollision,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+/// State, [`GameConfig`](config::GameConfig) and system ordering shared by all
+/// the other plugins, which rely on them.
+pub struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(config::GameConfigPlugin)
+            .add_state::<AppState>()
+            .add_state::<PauseState>()
+            .configure_sets(FixedUpdate, (
+                GameSet::Input,
+                GameSet::Movement,
+                GameSet::PaddleCollision,
+                GameSet::BallCollision,
+            ).chain().run_if(in_state(AppState::Level).and_then(in_state(PauseState::Running))))
+            .add_systems(FixedUpdate, apply_velocity.in_set(GameSet::Movement));
+    }
+}
+
+/// The whole game: [`CorePlugin`], [`LevelPlugin`], [`PaddlePlugin`],
+/// [`BallPlugin`], [`RewardPlugin`], [`EffectPlugin`], [`ScorePlugin`],
+/// [`HighScorePlugin`] and [`PausePlugin`].
+pub struct BreakoutPlugin;
+
+impl PluginGroup for BreakoutPlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CorePlugin)
+            .add(LevelPlugin)
+            .add(PaddlePlugin)
+            .add(BallPlugin)
+            .add(RewardPlugin)
+            .add(EffectPlugin)
+            .add(ScorePlugin)
+            .add(HighScorePlugin)
+            .add(PausePlugin)
+    }
+}
+
+fn apply_velocity(mut query: Query<(&mut Transform, &Velocity), Without<ball::Ball>>, time: Res<Time>) {
+    for (mut transform, velocity) in &mut query {
+        transform.translation.x += velocity.x * time.delta_seconds();
+        transform.translation.y += velocity.y * time.delta_seconds();
+    }
+}
@@ -0,0 +1,343 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    GameSet, Velocity,
+    ball::{Ball, Held, Substep},
+    collide,
+    config::{GameConfig, PADDLE_COLOR},
+    effect::{ActiveEffects, Effect},
+    level::{Collider, ColliderType},
+};
+
+/// How the paddle sends a ball back up.
+///
+/// `offset` is where the ball hit the paddle, from -1 at the left end to 1 at
+/// the right end. Every model keeps the speed of the incoming ball.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum PaddleReflection {
+    /// The horizontal speed grows linearly with the offset, up to 45 degrees
+    /// off vertical at the ends of the paddle.
+    #[default]
+    Linear,
+    /// The angle off vertical grows linearly with the offset, up to `max_angle`
+    /// degrees at the ends of the paddle.
+    AngleClamped {
+        max_angle: f32,
+    },
+    /// The paddle is split in equally wide segments, each one sending the ball
+    /// at a fixed angle off vertical in degrees, from left to right. The classic
+    /// Arkanoid paddle is `[-60, -45, -30, 30, 45, 60]`.
+    Segments {
+        angles: Vec<f32>,
+    },
+    /// Mirrors the ball like a wall would, then adds `factor` times the paddle's
+    /// horizontal velocity to the ball.
+    English {
+        factor: f32,
+    },
+}
+
+impl PaddleReflection {
+    /// Returns the velocity of a ball leaving the paddle, which always goes
+    /// upward and at least `min_angle` degrees above the horizontal.
+    pub fn reflect(&self, offset: f32, velocity: Vec2, paddle_velocity: Vec2, min_angle: f32) -> Vec2 {
+        let speed = velocity.length();
+        let offset = offset.clamp(-1.0, 1.0);
+
+        let direction = match self {
+            Self::Linear => {
+                let x = offset / std::f32::consts::SQRT_2;
+                Vec2::new(x, (1.0 - x * x).sqrt())
+            }
+            Self::AngleClamped { max_angle } => {
+                let angle = (offset * max_angle).to_radians();
+                Vec2::new(angle.sin(), angle.cos())
+            }
+            Self::Segments { angles } => {
+                if angles.is_empty() {
+                    Vec2::Y
+                } else {
+                    let index = (((offset + 1.0) / 2.0) * angles.len() as f32) as usize;
+                    let angle = angles[index.min(angles.len() - 1)].to_radians();
+                    Vec2::new(angle.sin(), angle.cos())
+                }
+            }
+            Self::English { factor } => {
+                let mirrored = Vec2::new(velocity.x, velocity.y.abs());
+                (mirrored + Vec2::new(paddle_velocity.x * factor, 0.0)).normalize_or_zero()
+            }
+        };
+
+        clamp_vertical_angle(direction, min_angle) * speed
+    }
+}
+
+/// Turns `direction` into an upward unit vector at least `min_angle` degrees
+/// above the horizontal, so that balls never get stuck bouncing sideways.
+pub fn clamp_vertical_angle(direction: Vec2, min_angle: f32) -> Vec2 {
+    let direction = Vec2::new(direction.x, direction.y.abs()).normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return Vec2::Y;
+    }
+
+    let min_angle = min_angle.to_radians();
+    if direction.y >= min_angle.sin() {
+        return direction;
+    }
+
+    let side = if direction.x < 0.0 { -1.0 } else { 1.0 };
+    Vec2::new(side * min_angle.cos(), min_angle.sin())
+}
+
+#[derive(Component)]
+pub struct Paddle;
+
+/// Where the paddle was at the start of the current tick and how fast it moved
+/// since, the paddle itself is teleported to the cursor.
+#[derive(Component, Default)]
+pub struct PaddleMotion {
+    pub previous: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Where the player points at in world coordinates, the paddle follows it.
+#[derive(Resource,Default, Deref, DerefMut)]
+pub struct CursorWorldCoords(pub Vec2);
+
+/// Sent for every ball landing on the paddle, whether it bounces off or is held.
+#[derive(Event, Debug)]
+pub struct PaddleHitEvent {
+    pub ball: Entity,
+}
+
+/// Spawns the paddle, moves it after the cursor and bounces the balls off it,
+/// or holds them on it during [`Effect::StickyPaddle`].
+pub struct PaddlePlugin;
+
+impl Plugin for PaddlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorWorldCoords>()
+            .init_resource::<ActiveEffects>()
+            .add_event::<PaddleHitEvent>()
+            .add_systems(Startup, setup_paddle)
+            .add_systems(Update, (
+                cursor_to_world_system,
+                resize_paddle.run_if(resource_changed::<GameConfig>().or_else(resource_changed::<ActiveEffects>())),
+            ))
+            .add_systems(FixedUpdate, (
+                move_paddle.in_set(GameSet::Input),
+                check_collider_paddle.in_set(GameSet::PaddleCollision),
+            ));
+    }
+}
+
+fn setup_paddle(mut commands: Commands, config: Res<GameConfig>) {
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform {
+                translation: config.paddle.start.extend(0.0),
+                scale: config.paddle.size.extend(0.0),
+                ..default()
+            },
+            sprite: Sprite {
+                color: PADDLE_COLOR,
+                ..default()
+            },
+            ..default()
+        },
+        Paddle,
+        PaddleMotion {
+            previous: config.paddle.start,
+            ..default()
+        },
+        Collider(ColliderType::Paddle),
+    ));
+}
+
+fn resize_paddle(mut query: Query<&mut Transform, With<Paddle>>, effects: Res<ActiveEffects>, config: Res<GameConfig>) {
+    for mut transform in &mut query {
+        transform.scale = Vec2::new(effects.paddle_width(&config), config.paddle.size.y).extend(0.0);
+    }
+}
+
+fn move_paddle(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut PaddleMotion), With<Paddle>>,
+    cursor_world_coords: Res<CursorWorldCoords>,
+    config: Res<GameConfig>,
+) {
+    let (mut paddle_transform, mut motion) = query.single_mut();
+    motion.previous = paddle_transform.translation.truncate();
+
+    if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
+        paddle_transform.translation.x -= config.paddle.speed * time.delta_seconds();
+    } else if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
+        paddle_transform.translation.x += config.paddle.speed * time.delta_seconds();
+    }
+
+    paddle_transform.translation.x = cursor_world_coords.x;
+
+    let right_edge = config.half_edge_size().x;
+    let left_bound = -right_edge + paddle_transform.scale.x / 2.0;
+    let right_bound = right_edge - paddle_transform.scale.x / 2.0;
+
+    paddle_transform.translation.x = paddle_transform.translation.x.clamp(left_bound, right_bound);
+
+    motion.velocity = if time.delta_seconds() > 0.0 {
+        (paddle_transform.translation.truncate() - motion.previous) / time.delta_seconds()
+    } else {
+        Vec2::ZERO
+    };
+}
+
+/// Sweeps every downward moving ball against the paddle, in the paddle's frame
+/// of reference so that the paddle's own displacement during the tick counts
+/// too. A ball is deflected at most once, since it leaves moving upward.
+fn check_collider_paddle(
+    mut commands: Commands,
+    paddle_query: Query<(&Transform, &PaddleMotion), With<Paddle>>,
+    mut ball_query: Query<(Entity, &Transform, &mut Velocity, &mut Substep), (With<Ball>, Without<Held>, Without<Paddle>)>,
+    effects: Res<ActiveEffects>,
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut hit_events: EventWriter<PaddleHitEvent>,
+) {
+    let config = &game_config.paddle;
+    let (paddle_transform, motion) = paddle_query.single();
+    let paddle_size = paddle_transform.scale.truncate();
+    let sticky = effects.is_active(Effect::StickyPaddle);
+
+    for (ball, ball_transform, mut velocity, mut substep) in &mut ball_query {
+        if velocity.y >= 0.0 {
+            continue
+        }
+
+        let Some(contact) = collide::time_of_collide_circle_rect(
+            ball_transform.translation.truncate(),
+            ball_transform.scale.x * 0.5,
+            velocity.0 - motion.velocity,
+            motion.previous,
+            paddle_size,
+        ) else {
+            continue
+        };
+
+        // side and bottom hits mean the ball already went past the paddle
+        if contact.toi > time.delta_seconds() || contact.normal.y <= 0.0 {
+            continue
+        }
+
+        let paddle_x = motion.previous.x + motion.velocity.x * contact.toi;
+        let ball_x = ball_transform.translation.x + velocity.x * contact.toi;
+        let offset = ((ball_x - paddle_x) / (paddle_size.x / 2.0)).clamp(-1.0, 1.0);
+        hit_events.send(PaddleHitEvent { ball });
+
+        if sticky {
+            // carry_held_balls puts it on the paddle from the next tick on
+            commands.entity(ball).insert(Held {
+                offset: offset * paddle_size.x / 2.0,
+                timer: Timer::from_seconds(game_config.ball.auto_launch, TimerMode::Once),
+            });
+            velocity.0 = Vec2::ZERO;
+            continue
+        }
+
+        // check_collider_ball moves the ball on from the contact with its new velocity
+        let pos = ball_transform.translation.truncate() + velocity.0 * contact.toi;
+        substep.0 = Some((pos, contact.toi));
+        velocity.0 = config.reflection.reflect(offset, velocity.0, motion.velocity, config.min_bounce_angle);
+    }
+}
+
+/// Follows the last touch, or the mouse cursor while it is inside the window.
+/// Does nothing without a primary window and camera, e.g. in headless runs.
+fn cursor_to_world_system(
+    mut cursor_world_coords: ResMut<CursorWorldCoords>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    touches: Option<Res<Touches>>,
+ ){
+    let (Ok((camera, camera_transform)), Ok(window)) = (q_camera.get_single(), q_window.get_single()) else {
+        return;
+    };
+
+    let touch_position = touches.and_then(|touches| touches.iter().last().map(|touch| touch.position()));
+    let Some(cursor_position) = touch_position.or_else(|| window.cursor_position()) else {
+        return;
+    };
+
+    let Some(point) = camera.viewport_to_world_2d(camera_transform, cursor_position)
+    else {
+       return;
+    };
+
+    cursor_world_coords.0 = point;
+ }
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+
+    use super::{PaddleReflection, clamp_vertical_angle};
+
+    fn assert_vec_eq(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_linear_keeps_speed() {
+        let velocity = Vec2::new(200.0, -200.0);
+        let speed = velocity.length();
+
+        let center = PaddleReflection::Linear.reflect(0.0, velocity, Vec2::ZERO, 20.0);
+        assert_vec_eq(center, Vec2::new(0.0, speed));
+
+        let right = PaddleReflection::Linear.reflect(1.0, velocity, Vec2::ZERO, 20.0);
+        assert_vec_eq(right, Vec2::new(200.0, 200.0));
+    }
+
+    #[test]
+    fn test_angle_clamped() {
+        let reflection = PaddleReflection::AngleClamped { max_angle: 60.0 };
+        let out = reflection.reflect(-1.0, Vec2::new(0.0, -10.0), Vec2::ZERO, 20.0);
+        assert_vec_eq(out, Vec2::new(-10.0 * 60f32.to_radians().sin(), 10.0 * 60f32.to_radians().cos()));
+    }
+
+    #[test]
+    fn test_segments() {
+        let reflection = PaddleReflection::Segments {
+            angles: vec![-60.0, -45.0, -30.0, 30.0, 45.0, 60.0],
+        };
+        let velocity = Vec2::new(0.0, -10.0);
+
+        let left = reflection.reflect(-1.0, velocity, Vec2::ZERO, 20.0);
+        assert!((left.x.atan2(left.y).to_degrees() + 60.0).abs() < 1e-3);
+
+        let inner_right = reflection.reflect(0.1, velocity, Vec2::ZERO, 20.0);
+        assert!((inner_right.x.atan2(inner_right.y).to_degrees() - 30.0).abs() < 1e-3);
+
+        let right = reflection.reflect(1.0, velocity, Vec2::ZERO, 20.0);
+        assert!((right.x.atan2(right.y).to_degrees() - 60.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_english_and_min_angle() {
+        let reflection = PaddleReflection::English { factor: 0.5 };
+
+        let still = reflection.reflect(0.0, Vec2::new(3.0, -4.0), Vec2::ZERO, 20.0);
+        assert_vec_eq(still, Vec2::new(3.0, 4.0));
+
+        // a fast paddle would send the ball almost sideways, the minimum angle keeps it climbing
+        let fast = reflection.reflect(0.0, Vec2::new(3.0, -4.0), Vec2::new(400.0, 0.0), 20.0);
+        assert!((fast.length() - 5.0).abs() < 1e-3);
+        assert!((fast.y / fast.length() - 20f32.to_radians().sin()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_clamp_vertical_angle() {
+        assert_vec_eq(clamp_vertical_angle(Vec2::new(-1.0, 0.0), 30.0), Vec2::new(-(30f32.to_radians().cos()), 0.5));
+        assert_vec_eq(clamp_vertical_angle(Vec2::new(0.0, -1.0), 30.0), Vec2::Y);
+    }
+}
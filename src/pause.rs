@@ -0,0 +1,111 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{AppState, PauseState};
+
+/// Pauses the level on P or Escape and when the window loses focus, the same
+/// keys resume it.
+///
+/// While paused the virtual clock is stopped, so `FixedUpdate` does not tick
+/// and nothing reading `Time` in `Update`, like the reward cooldowns, moves on.
+/// Sounds being played are paused too. Leaving the level always resumes.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WindowFocused>()
+            .add_systems(Update, toggle_pause.run_if(in_state(AppState::Level)))
+            .add_systems(OnExit(AppState::Level), resume)
+            .add_systems(OnEnter(PauseState::Paused), pause_time_and_audio)
+            .add_systems(OnExit(PauseState::Paused), resume_time_and_audio);
+    }
+}
+
+fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut focus_events: EventReader<WindowFocused>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    let focus_lost = focus_events.read().any(|event| !event.focused);
+
+    if keyboard_input.any_just_pressed([KeyCode::P, KeyCode::Escape]) {
+        next_state.set(match state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    } else if focus_lost {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+fn resume(mut next_state: ResMut<NextState<PauseState>>) {
+    next_state.set(PauseState::Running);
+}
+
+fn pause_time_and_audio(mut time: ResMut<Time<Virtual>>, sinks: Query<&AudioSink>) {
+    time.pause();
+    for sink in &sinks {
+        sink.pause();
+    }
+}
+
+fn resume_time_and_audio(mut time: ResMut<Time<Virtual>>, sinks: Query<&AudioSink>) {
+    time.unpause();
+    for sink in &sinks {
+        sink.play();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{prelude::*, window::WindowFocused};
+
+    use super::PausePlugin;
+    use crate::{AppState, PauseState};
+
+    fn paused(app: &App) -> bool {
+        *app.world.resource::<State<PauseState>>().get() == PauseState::Paused
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.update();
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.release(key);
+        input.clear();
+        app.update();
+    }
+
+    #[test]
+    fn test_pause_stops_virtual_time() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, PausePlugin))
+            .add_state::<AppState>()
+            .add_state::<PauseState>()
+            .init_resource::<Input<KeyCode>>();
+        app.world.resource_mut::<NextState<AppState>>().set(AppState::Level);
+        app.update();
+
+        app.world.send_event(WindowFocused {
+            window: Entity::PLACEHOLDER,
+            focused: false,
+        });
+        app.update();
+        app.update();
+        assert!(paused(&app));
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+
+        press(&mut app, KeyCode::P);
+        assert!(!paused(&app));
+        assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+
+        // leaving the level resumes
+        press(&mut app, KeyCode::Escape);
+        assert!(paused(&app));
+        app.world.resource_mut::<NextState<AppState>>().set(AppState::GameOver);
+        app.update();
+        app.update();
+        assert!(!paused(&app));
+        assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+    }
+}
@@ -0,0 +1,458 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::{prelude::*, sprite::collide_aabb::collide, utils::HashMap};
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    AppState, GameSet, Velocity,
+    ball::{Ball, BallBundle, CollisionEvent, GenBallController, Held},
+    config::{BALL_COLOR, DROP_TABLES_PATH, GameConfig, REWARDS_PATH},
+    drop_table::{DropTableState, DropTables},
+    effect::{ActiveEffects, Effect},
+    json_plugin::JsonAssetPlugin,
+    level::LevelRules,
+    paddle::Paddle,
+};
+
+/// What catching a reward does, with its parameters.
+///
+/// Every kind is applied by the systems reading [`ReceiveRewardEvent`] in the
+/// plugin it concerns: the balls here, [`Effect`]s in the
+/// [`EffectPlugin`](crate::effect::EffectPlugin) and lives in the
+/// [`ScorePlugin`](crate::score::ScorePlugin). The match on the kind in
+/// `read_receive_reward_events` lists them all, so a new kind does not build
+/// until it is given a place there, and a handler wherever it is applied.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RewardKind {
+    /// Every ball in play splits into `balls` more.
+    Split { balls: u32 },
+    /// `balls` new balls are held on the paddle.
+    Serve { balls: u32 },
+    /// Starts a timed effect.
+    Effect(Effect),
+    ExtraLife,
+}
+
+impl RewardKind {
+    /// The same kind with its parameter set to `param`, for the kinds that
+    /// have one.
+    pub fn with_param(self, param: u32) -> Self {
+        match self {
+            RewardKind::Split { .. } => RewardKind::Split { balls: param },
+            RewardKind::Serve { .. } => RewardKind::Serve { balls: param },
+            kind => kind,
+        }
+    }
+}
+
+/// The rewards drop tables can drop, by name.
+///
+/// Read from [`REWARDS_PATH`].
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct RewardRegistry {
+    pub rewards: HashMap<String, RewardData>,
+}
+
+impl RewardRegistry {
+    pub fn get(&self, name: &str) -> Option<&RewardData> {
+        self.rewards.get(name)
+    }
+}
+
+/// A reward and how it falls. `texture` is drawn tinted with `color`, or a
+/// plain block of `color` without one. `fall_speed` and `size`, which is also
+/// the size the paddle catches it at, default to the [`RewardConfig`](crate::config::RewardConfig)'s.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RewardData {
+    pub kind: RewardKind,
+    #[serde(default)]
+    pub texture: Option<String>,
+    #[serde(default = "default_reward_color")]
+    pub color: Color,
+    #[serde(default)]
+    pub fall_speed: Option<f32>,
+    #[serde(default)]
+    pub size: Option<Vec2>,
+}
+
+fn default_reward_color() -> Color {
+    Color::WHITE
+}
+
+/// A falling reward, caught by the paddle when they overlap at `size`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RewardBrick {
+    pub kind: RewardKind,
+    pub size: Vec2,
+}
+
+#[derive(Bundle)]
+pub struct RewardBundle {
+    sprite: SpriteBundle,
+    reward: RewardBrick,
+    velocity: Velocity,
+}
+
+impl RewardBundle {
+    pub fn new(pos: Vec2, reward: RewardBrick, texture: Handle<Image>, color: Color, fall_speed: f32) -> Self{
+        Self {
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(reward.size),
+                    ..default()
+                },
+                transform: Transform::from_translation(pos.extend(0.0)),
+                texture,
+                ..default()
+            },
+            reward,
+            velocity: Velocity(Vec2::new(0.0, -fall_speed)),
+        }
+    }
+}
+
+/// Drops the reward named `reward` at `pos`, as `kind` once its parameter is
+/// drawn, from the drop table named `table`.
+#[derive(Event, Clone, Debug)]
+pub struct GenRewardEvent {
+    pub pos: Vec2,
+    pub reward: String,
+    pub kind: RewardKind,
+    pub table: String,
+}
+
+/// The drop table a falling reward came from.
+#[derive(Component, Deref)]
+pub struct DroppedFrom(pub String);
+
+/// Sent when the paddle catches a falling reward.
+#[derive(Event, Deref, Debug, Clone, Copy)]
+pub struct ReceiveRewardEvent(pub RewardKind);
+
+/// The state of every drop table rolled so far.
+#[derive(Resource, Default)]
+pub struct RewardDrops {
+    pub tables: HashMap<String, DropTableState>,
+}
+
+#[derive(Resource)]
+struct RewardAssets {
+    drop_tables: Handle<DropTables>,
+    rewards: Handle<RewardRegistry>,
+}
+
+/// Drops rewards from broken bricks, as their [`DropTables`] decide, and
+/// applies the ones the paddle catches. Rewards still falling when the level
+/// ends are removed, and forgotten along with the drop tables' state on
+/// restart.
+pub struct RewardPlugin;
+
+impl Plugin for RewardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+                JsonAssetPlugin::<DropTables>::new(&["droptables.json"]),
+                JsonAssetPlugin::<RewardRegistry>::new(&["rewards.json"]),
+            ))
+            .init_resource::<RewardDrops>()
+            .init_resource::<ActiveEffects>()
+            .add_systems(Startup, load_reward_assets)
+            .add_event::<GenRewardEvent>()
+            .add_event::<ReceiveRewardEvent>()
+            .add_systems(Update, (
+                drop_rewards,
+                read_gen_reward_events,
+                read_receive_reward_events,
+            ))
+            .add_systems(OnExit(AppState::Level), despawn_rewards)
+            .add_systems(OnExit(AppState::GameOver), reset_rewards)
+            .add_systems(FixedUpdate, check_receive_rewards.in_set(GameSet::PaddleCollision));
+    }
+}
+
+fn load_reward_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(RewardAssets {
+        drop_tables: asset_server.load(DROP_TABLES_PATH),
+        rewards: asset_server.load(REWARDS_PATH),
+    });
+}
+
+/// Rolls the drop table of every broken brick: the one of its type, else the
+/// one of the level, else the default one.
+fn drop_rewards(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut gen_reward_events: EventWriter<GenRewardEvent>,
+    mut drops: ResMut<RewardDrops>,
+    falling_query: Query<&DroppedFrom>,
+    drop_tables: Res<Assets<DropTables>>,
+    registries: Res<Assets<RewardRegistry>>,
+    handles: Res<RewardAssets>,
+    rules: Res<LevelRules>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut dropped: Local<HashMap<String, u32>>,
+) {
+    let (Some(tables), Some(registry)) = (drop_tables.get(&handles.drop_tables), registries.get(&handles.rewards)) else {
+        collision_events.clear();
+        return
+    };
+
+    // rewards sent this frame are not falling yet
+    dropped.clear();
+    let mut rng = rand::thread_rng();
+    for event in collision_events.read() {
+        let name = event.drop_table.as_ref()
+            .or(rules.drop_table.as_ref())
+            .unwrap_or(&config.reward.drop_table);
+        let Some(table) = tables.get(name) else {
+            warn!("unknown drop table \"{}\"", name);
+            continue
+        };
+
+        let falling = falling_query.iter().filter(|source| source.0 == *name).count() as u32
+            + dropped.get(name).copied().unwrap_or(0);
+        let state = drops.tables.entry(name.clone()).or_default();
+        let Some((reward, param)) = table.roll(state, time.elapsed_seconds(), falling, &mut rng) else {
+            continue
+        };
+        let Some(data) = registry.get(reward) else {
+            warn!("unknown reward \"{}\" in drop table \"{}\"", reward, name);
+            continue
+        };
+
+        *dropped.entry(name.clone()).or_default() += 1;
+        gen_reward_events.send(GenRewardEvent {
+            pos: event.pos,
+            reward: reward.to_string(),
+            kind: param.map_or(data.kind, |param| data.kind.with_param(param)),
+            table: name.clone(),
+        });
+    }
+}
+
+fn despawn_rewards(mut commands: Commands, reward_query: Query<Entity, With<RewardBrick>>) {
+    for entity in &reward_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn reset_rewards(
+    mut drops: ResMut<RewardDrops>,
+    mut gen_reward_events: ResMut<Events<GenRewardEvent>>,
+    mut receive_reward_events: ResMut<Events<ReceiveRewardEvent>>,
+) {
+    *drops = RewardDrops::default();
+    gen_reward_events.clear();
+    receive_reward_events.clear();
+}
+
+fn read_gen_reward_events(
+    mut commands: Commands,
+    mut gen_reward_events: EventReader<GenRewardEvent>,
+    registries: Res<Assets<RewardRegistry>>,
+    handles: Res<RewardAssets>,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
+    let Some(registry) = registries.get(&handles.rewards) else {
+        gen_reward_events.clear();
+        return
+    };
+
+    for event in gen_reward_events.read() {
+        debug!("gen reward: {:?}", event);
+        let Some(data) = registry.get(&event.reward) else {
+            continue
+        };
+
+        let reward = RewardBrick {
+            kind: event.kind,
+            size: data.size.unwrap_or(config.reward.size),
+        };
+        let texture = data.texture.as_ref().map_or_else(default, |path| asset_server.load(path));
+        commands.spawn((
+            RewardBundle::new(event.pos, reward, texture, data.color, data.fall_speed.unwrap_or(config.reward.fall_speed)),
+            DroppedFrom(event.table.clone()),
+        ));
+    }
+}
+
+/// Applies the rewards caught by the paddle and removes the ones that fell out
+/// of the arena.
+fn check_receive_rewards(
+    mut commands: Commands,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    reward_query: Query<(&Transform, Entity, &RewardBrick)>,
+    mut receive_reward_event: EventWriter<ReceiveRewardEvent>,
+    config: Res<GameConfig>,
+) {
+    let paddle = paddle_query.single();
+    let bottom_edge = -config.half_edge_size().y;
+    for (&transform, reward_entity, reward) in &reward_query {
+        if transform.translation.y + reward.size.y / 2.0 < bottom_edge {
+            commands.entity(reward_entity).despawn();
+            continue
+        }
+        if collide(transform.translation, reward.size, paddle.translation, paddle.scale.truncate()).is_none() {
+            continue
+        }
+        receive_reward_event.send(ReceiveRewardEvent(reward.kind));
+        commands.entity(reward_entity).despawn();
+    }
+}
+
+/// Applies the ball rewards. Splitting stops at [`BallConfig::max_count`](crate::config::BallConfig::max_count) balls.
+fn read_receive_reward_events(
+    mut commands: Commands,
+    mut controller: ResMut<GenBallController>,
+    mut receive_reward_event: EventReader<ReceiveRewardEvent>,
+    ball_query: Query<(&Transform, &Velocity), (With<Ball>, Without<Held>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    effects: Res<ActiveEffects>,
+    config: Res<GameConfig>,
+) {
+    let ball = &config.ball;
+    let speed = effects.ball_speed(&config);
+    for &event in receive_reward_event.read() {
+        debug!("receive reward event:{:?}", event.0);
+        match event.0 {
+            RewardKind::Split { balls } => {
+                let mut room = (ball.max_count - controller.ball_count).max(0) as u32;
+                if room == 0 {
+                    continue
+                }
+                let mesh_handler = meshes.add(shape::Circle::default().into());
+                let material_handler = materials.add(ColorMaterial::from(BALL_COLOR));
+                let mut rng = rand::thread_rng();
+                for (transform, ball_velocity) in &ball_query {
+                    let count = balls.min(room);
+                    for _ in 0..count {
+                        // up to 45 degrees off vertical, heading the same way up or down as the split ball
+                        let angle = rng.gen_range(-FRAC_PI_4..FRAC_PI_4);
+                        let mut direction = Vec2::new(angle.sin(), angle.cos());
+                        if ball_velocity.y < 0.0 {
+                            direction.y = -direction.y
+                        }
+                        commands.spawn(BallBundle::new(
+                            mesh_handler.clone().into(),
+                            material_handler.clone(),
+                            transform.translation,
+                            transform.scale.x * 0.5,
+                            direction * speed,
+                        ));
+                    }
+                    controller.ball_count += count as i32;
+                    room -= count;
+                    if room == 0 {
+                        break
+                    }
+                }
+            },
+            RewardKind::Serve { balls } => {
+                let paddle_transform = paddle_query.single();
+                for _ in 0..balls {
+                    commands.spawn(BallBundle::on_paddle(&mut meshes, &mut materials, paddle_transform, ball));
+                    controller.ball_count += 1;
+                }
+            },
+            // applied by start_reward_effects and grant_extra_lives
+            RewardKind::Effect(_) | RewardKind::ExtraLife => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{read_receive_reward_events, ReceiveRewardEvent, RewardKind};
+    use crate::{
+        Velocity,
+        ball::{launch_held_balls, Ball, BallBundle, GenBallController, LaunchEvent},
+        config::GameConfig,
+        effect::{ActiveEffects, Effect},
+        paddle::{Paddle, PaddleMotion},
+    };
+
+    #[test]
+    fn test_split_balls_move_as_fast_as_served_balls() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameConfig>()
+            .init_resource::<ActiveEffects>()
+            .init_resource::<GenBallController>()
+            .insert_resource(Assets::<Mesh>::default())
+            .insert_resource(Assets::<ColorMaterial>::default())
+            .add_event::<ReceiveRewardEvent>()
+            .add_event::<LaunchEvent>()
+            .add_systems(Update, (read_receive_reward_events, launch_held_balls).chain());
+
+        let config = app.world.resource::<GameConfig>().clone();
+        app.world.resource_mut::<ActiveEffects>().start(Effect::FastBall, &config.effects);
+        app.world.spawn((
+            Transform::from_xyz(0.0, -200.0, 0.0).with_scale(Vec3::new(80.0, 10.0, 0.0)),
+            PaddleMotion::default(),
+            Paddle,
+        ));
+        let split = app.world.spawn(BallBundle::new(
+            default(),
+            default(),
+            Vec3::ZERO,
+            config.ball.radius,
+            Vec2::new(0.0, -config.ball.speed),
+        )).id();
+
+        app.world.send_event(ReceiveRewardEvent(RewardKind::Serve { balls: 1 }));
+        app.world.send_event(ReceiveRewardEvent(RewardKind::Split { balls: 4 }));
+        app.update();
+        app.world.send_event(LaunchEvent);
+        app.update();
+
+        let speed = app.world.resource::<ActiveEffects>().ball_speed(&config);
+        let mut balls = app.world.query_filtered::<(Entity, &Velocity), With<Ball>>();
+        let speeds: Vec<f32> = balls.iter(&app.world)
+            .filter(|&(entity, _)| entity != split)
+            .map(|(_, velocity)| velocity.length())
+            .collect();
+        assert_eq!(speeds.len(), 5, "one served and four split balls");
+        for ball_speed in speeds {
+            assert!((ball_speed - speed).abs() < 1e-3, "{} != {}", ball_speed, speed);
+        }
+    }
+
+    #[test]
+    fn test_split_stops_at_max_count() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameConfig>()
+            .init_resource::<ActiveEffects>()
+            .insert_resource(GenBallController { ball_count: 2 })
+            .insert_resource(Assets::<Mesh>::default())
+            .insert_resource(Assets::<ColorMaterial>::default())
+            .add_event::<ReceiveRewardEvent>()
+            .add_systems(Update, read_receive_reward_events);
+        app.world.resource_mut::<GameConfig>().ball.max_count = 5;
+
+        let config = app.world.resource::<GameConfig>().clone();
+        for x in [-10.0, 10.0] {
+            app.world.spawn(BallBundle::new(
+                default(),
+                default(),
+                Vec3::new(x, 0.0, 0.0),
+                config.ball.radius,
+                Vec2::new(0.0, config.ball.speed),
+            ));
+        }
+
+        app.world.send_event(ReceiveRewardEvent(RewardKind::Split { balls: 2 }));
+        app.update();
+
+        let balls = app.world.query_filtered::<(), With<Ball>>().iter(&app.world).count();
+        assert_eq!(balls, 5);
+        assert_eq!(app.world.resource::<GenBallController>().ball_count, 5);
+    }
+}
@@ -0,0 +1,266 @@
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::{
+    AppState,
+    ball::CollisionEvent,
+    brick::ExplosionEvent,
+    config::{GameConfig, ScoreConfig},
+    high_score::NameEntry,
+    level::LevelRules,
+    paddle::PaddleHitEvent,
+    reward::{ReceiveRewardEvent, RewardKind},
+};
+
+#[derive(Resource, Default)]
+pub struct Score {
+    pub val: i32,
+}
+
+/// What points were scored for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreReason {
+    /// A brick broke at `pos`, its points times the combo `multiplier`.
+    Brick { pos: Vec2, multiplier: i32 },
+    /// A blast at `pos` broke `bricks` bricks.
+    Explosion { pos: Vec2, bricks: u32 },
+    /// The level was cleared `seconds` under par.
+    ClearTime { seconds: f32 },
+}
+
+/// Sent for every change of the [`Score`], with the points added, the new
+/// total and what they were scored for.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ScoreChangedEvent {
+    pub points: i32,
+    pub total: i32,
+    pub reason: ScoreReason,
+}
+
+/// Bricks broken since a ball last touched the paddle, see
+/// [`ScoreConfig::combo_step`].
+#[derive(Resource, Default, Debug)]
+pub struct Combo {
+    pub hits: u32,
+}
+
+impl Combo {
+    /// The multiplier of the points of the next brick broken.
+    pub fn multiplier(&self, config: &ScoreConfig) -> i32 {
+        (1 + self.hits / config.combo_step.max(1)).min(config.max_multiplier.max(1)) as i32
+    }
+}
+
+/// Time spent playing the current level, pauses excluded.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct LevelTime(pub Stopwatch);
+
+/// Balls the player can still lose, see [`GameConfig::lives`].
+#[derive(Resource, Deref, DerefMut)]
+pub struct Lives(pub u32);
+
+impl FromWorld for Lives {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.get_resource::<GameConfig>().map_or(GameConfig::default().lives, |config| config.lives))
+    }
+}
+
+/// Played for every frame in which bricks were broken, when present.
+#[derive(Resource)]
+pub struct CollisionSound(pub Handle<AudioSource>);
+
+/// Counts the points of the broken bricks with the combo, explosion and level
+/// clear bonuses of [`ScoreConfig`], and the lives left, adding the extra lives
+/// caught. Every change of the score is announced by a [`ScoreChangedEvent`].
+/// Once the game is over and the name of any high score entered, a key press,
+/// click or touch restarts the current level with the score and lives reset.
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<Lives>()
+            .init_resource::<Combo>()
+            .init_resource::<LevelTime>()
+            .add_event::<CollisionEvent>()
+            .add_event::<ExplosionEvent>()
+            .add_event::<PaddleHitEvent>()
+            .add_event::<ReceiveRewardEvent>()
+            .add_event::<ScoreChangedEvent>()
+            .add_systems(Update, (
+                (break_combo, read_collision_events, read_explosion_events).chain(),
+                tick_level_time.run_if(in_state(AppState::Level)),
+                grant_extra_lives,
+                apply_lives_config.run_if(resource_changed::<GameConfig>()),
+                restart_on_input.run_if(in_state(AppState::GameOver).and_then(not(resource_exists::<NameEntry>()))),
+            ))
+            .add_systems(OnEnter(AppState::Level), start_level)
+            .add_systems(OnEnter(AppState::LevelCleared), grant_time_bonus)
+            .add_systems(OnExit(AppState::GameOver), reset_score);
+    }
+}
+
+fn add_points(score: &mut Score, score_events: &mut EventWriter<ScoreChangedEvent>, points: i32, reason: ScoreReason) {
+    score.val += points;
+    score_events.send(ScoreChangedEvent {
+        points,
+        total: score.val,
+        reason,
+    });
+}
+
+fn start_level(mut combo: ResMut<Combo>, mut level_time: ResMut<LevelTime>) {
+    combo.hits = 0;
+    level_time.reset();
+}
+
+fn tick_level_time(mut level_time: ResMut<LevelTime>, time: Res<Time>) {
+    level_time.tick(time.delta());
+}
+
+fn break_combo(mut hit_events: EventReader<PaddleHitEvent>, mut combo: ResMut<Combo>) {
+    if hit_events.read().count() > 0 {
+        combo.hits = 0;
+    }
+}
+
+fn read_collision_events(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    config: Res<GameConfig>,
+    sound: Option<Res<CollisionSound>>,
+    mut score_events: EventWriter<ScoreChangedEvent>,
+) {
+    if collision_events.is_empty() {
+        return
+    }
+
+    for event in collision_events.read() {
+        let multiplier = combo.multiplier(&config.score);
+        combo.hits += 1;
+        add_points(&mut score, &mut score_events, event.score * multiplier, ScoreReason::Brick {
+            pos: event.pos,
+            multiplier,
+        });
+    }
+
+    if let Some(sound) = sound {
+        commands.spawn(AudioBundle{
+            source: sound.0.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+/// Each brick past the first broken by the same blast is worth the
+/// explosion bonus, on top of its own points.
+fn read_explosion_events(
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut score: ResMut<Score>,
+    config: Res<GameConfig>,
+    mut score_events: EventWriter<ScoreChangedEvent>,
+) {
+    for event in explosion_events.read() {
+        if event.bricks > 1 {
+            let points = (event.bricks - 1) as i32 * config.score.explosion_bonus;
+            add_points(&mut score, &mut score_events, points, ScoreReason::Explosion {
+                pos: event.pos,
+                bricks: event.bricks,
+            });
+        }
+    }
+}
+
+pub(crate) fn grant_time_bonus(
+    mut score: ResMut<Score>,
+    level_time: Res<LevelTime>,
+    rules: Res<LevelRules>,
+    config: Res<GameConfig>,
+    mut score_events: EventWriter<ScoreChangedEvent>,
+) {
+    let par_time = rules.par_time.unwrap_or(config.score.par_time);
+    let seconds = par_time - level_time.elapsed_secs();
+    let points = (seconds.max(0.0) * config.score.time_bonus as f32) as i32;
+    if points > 0 {
+        add_points(&mut score, &mut score_events, points, ScoreReason::ClearTime { seconds });
+    }
+}
+
+fn grant_extra_lives(mut receive_reward_events: EventReader<ReceiveRewardEvent>, mut lives: ResMut<Lives>) {
+    for event in receive_reward_events.read() {
+        if event.0 == RewardKind::ExtraLife {
+            lives.0 += 1;
+        }
+    }
+}
+
+fn restart_on_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.any_just_pressed([KeyCode::R, KeyCode::Space, KeyCode::Return])
+        || mouse_input.just_pressed(MouseButton::Left)
+        || touches.any_just_pressed()
+    {
+        state.set(AppState::Loading);
+    }
+}
+
+/// Gives or takes the lives added to or removed from the configuration.
+fn apply_lives_config(mut lives: ResMut<Lives>, config: Res<GameConfig>, mut configured: Local<Option<u32>>) {
+    if let Some(previous) = configured.replace(config.lives) {
+        lives.0 = (lives.0 + config.lives).saturating_sub(previous);
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>, mut lives: ResMut<Lives>, config: Res<GameConfig>) {
+    *score = Score::default();
+    lives.0 = config.lives;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{break_combo, read_collision_events, read_explosion_events, Combo, Score, ScoreChangedEvent, ScoreReason};
+    use crate::{ball::CollisionEvent, brick::ExplosionEvent, config::GameConfig, paddle::PaddleHitEvent};
+
+    #[test]
+    fn test_combo_and_explosion_bonus() {
+        let mut app = App::new();
+        app.init_resource::<GameConfig>()
+            .init_resource::<Score>()
+            .init_resource::<Combo>()
+            .add_event::<CollisionEvent>()
+            .add_event::<ExplosionEvent>()
+            .add_event::<PaddleHitEvent>()
+            .add_event::<ScoreChangedEvent>()
+            .add_systems(Update, (break_combo, read_collision_events, read_explosion_events).chain());
+
+        let config = app.world.resource::<GameConfig>().score.clone();
+        let bricks = config.combo_step + 1;
+        for _ in 0..bricks {
+            app.world.send_event(CollisionEvent { score: 3, ..default() });
+        }
+        app.world.send_event(ExplosionEvent { pos: Vec2::ZERO, bricks: 3 });
+        app.update();
+
+        let expected = 3 * config.combo_step as i32 + 3 * 2 + 2 * config.explosion_bonus;
+        assert_eq!(app.world.resource::<Score>().val, expected);
+        let mut reader = app.world.resource::<Events<ScoreChangedEvent>>().get_reader();
+        let events: Vec<ScoreChangedEvent> = reader.read(app.world.resource()).copied().collect();
+        assert_eq!(events.len(), bricks as usize + 1);
+        assert_eq!(events.last().unwrap().total, expected);
+        assert!(matches!(events[bricks as usize - 1].reason, ScoreReason::Brick { multiplier: 2, .. }));
+
+        // touching the paddle starts the combo over
+        app.world.send_event(PaddleHitEvent { ball: Entity::PLACEHOLDER });
+        app.world.send_event(CollisionEvent { score: 3, ..default() });
+        app.update();
+        assert_eq!(app.world.resource::<Score>().val, expected + 3);
+        assert_eq!(app.world.resource::<Combo>().hits, 1);
+    }
+}
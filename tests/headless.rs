@@ -0,0 +1,89 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use breakout::{
+    AppState, BreakoutPlugin, EffectPlugin, HighScorePlugin, PausePlugin, RewardPlugin, ScorePlugin,
+    ball::Ball,
+    config::{GameConfig, HEADLESS_LOAD_TIMEOUT},
+    headless::{run_headless, HeadlessPlugin},
+    high_score::NameEntry,
+    score::{Lives, Score},
+};
+
+#[test]
+fn headless_run_breaks_bricks() {
+    let report = run_headless(64 * 20).expect("headless run");
+
+    assert_eq!(report.ticks, 64 * 20);
+    assert!(report.score > 0, "no brick broken in {}", report);
+    assert!(report.balls > 0);
+}
+
+#[test]
+fn headless_game_over_with_a_score() {
+    let mut app = App::new();
+    app.add_plugins((HeadlessPlugin, BreakoutPlugin));
+    let state = |app: &App| *app.world.resource::<State<AppState>>().get();
+
+    let start = std::time::Instant::now();
+    while state(&app) != AppState::Level {
+        assert!(start.elapsed() < HEADLESS_LOAD_TIMEOUT, "level did not load");
+        app.update();
+    }
+    for _ in 0..64 * 60 {
+        if app.world.resource::<Score>().val > 0 {
+            break
+        }
+        app.update();
+    }
+    assert!(app.world.resource::<Score>().val > 0, "no brick broken");
+
+    // drop every ball on the last life
+    app.world.resource_mut::<Lives>().0 = 1;
+    let bottom = -app.world.resource::<GameConfig>().half_edge_size().y;
+    for _ in 0..64 {
+        if state(&app) == AppState::GameOver {
+            break
+        }
+        let mut balls = app.world.query_filtered::<&mut Transform, With<Ball>>();
+        for mut transform in balls.iter_mut(&mut app.world) {
+            transform.translation.y = bottom - 100.0;
+        }
+        app.update();
+    }
+    assert_eq!(state(&app), AppState::GameOver);
+
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(app.world.get_resource::<NameEntry>().is_none(), "headless runs do not ask for a name");
+}
+
+/// Plays a few seconds of a level with the plugins of `group`.
+fn play(group: PluginGroupBuilder) {
+    let mut app = App::new();
+    app.add_plugins((HeadlessPlugin, group));
+
+    let start = std::time::Instant::now();
+    while *app.world.resource::<State<AppState>>().get() != AppState::Level {
+        assert!(start.elapsed() < HEADLESS_LOAD_TIMEOUT, "level did not load");
+        app.update();
+    }
+    for _ in 0..64 * 10 {
+        app.update();
+    }
+    assert!(app.world.query_filtered::<(), With<Ball>>().iter(&app.world).count() > 0);
+}
+
+#[test]
+fn headless_run_without_optional_plugins() {
+    play(BreakoutPlugin.build().disable::<RewardPlugin>());
+    play(BreakoutPlugin.build().disable::<EffectPlugin>());
+    play(BreakoutPlugin.build().disable::<ScorePlugin>());
+    play(BreakoutPlugin.build().disable::<HighScorePlugin>());
+    play(BreakoutPlugin.build().disable::<PausePlugin>());
+    play(BreakoutPlugin.build()
+        .disable::<RewardPlugin>()
+        .disable::<EffectPlugin>()
+        .disable::<ScorePlugin>()
+        .disable::<HighScorePlugin>()
+        .disable::<PausePlugin>());
+}